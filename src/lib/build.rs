@@ -5,7 +5,7 @@ extern crate serde_json;
 extern crate handlebars;
 
 use serde_json::Value;
-use handlebars::{Context, Handlebars};
+use handlebars::{Context, Handlebars, Helper, RenderContext, RenderError};
 use std::fs::File;
 
 fn main() {
@@ -43,6 +43,7 @@ fn expand() {
 fn render_handlebars() {
     let mut handlebars = Handlebars::new();
     handlebars.register_escape_fn(handlebars::no_escape);
+    handlebars.register_helper("aliases", Box::new(aliases));
     let raw_data: Value = serde_json::from_reader(File::open(&"src/lib/languages.json").unwrap()).unwrap();
     let data = Context::wraps(&raw_data);
     let mut source_template = File::open(&"src/lib/language/language_type.rs.hbs").expect("Can't find Template");
@@ -51,3 +52,39 @@ fn render_handlebars() {
         panic!("Failed to generate languages! ERROR: {:?}", err);
     }
 }
+
+/// Renders the match pattern of every lowercase name a language can be
+/// referred to by. ie. `"rust" | "rs"`. Takes the language's key, and its
+/// definition.
+fn aliases(_: &Context,
+           h: &Helper,
+           _: &Handlebars,
+           rc: &mut RenderContext)
+           -> Result<(), RenderError> {
+    let key = try!(h.param(0).ok_or_else(|| RenderError::new("aliases: Missing key.")));
+    let language = try!(h.param(1).ok_or_else(|| RenderError::new("aliases: Missing language.")));
+    let mut aliases: Vec<String> = Vec::new();
+
+    {
+        let mut push = |alias: Option<&str>| {
+            if let Some(alias) = alias {
+                let alias = format!("\"{}\"", alias.to_lowercase());
+                if !aliases.contains(&alias) {
+                    aliases.push(alias);
+                }
+            }
+        };
+
+        push(key.value().as_str());
+        push(language.value().find("name").and_then(Value::as_str));
+
+        if let Some(extensions) = language.value().find("extensions").and_then(Value::as_array) {
+            for extension in extensions {
+                push(extension.as_str());
+            }
+        }
+    }
+
+    try!(rc.writer.write(aliases.join(" | ").as_bytes()));
+    Ok(())
+}
@@ -0,0 +1,217 @@
+use std::cmp::{self, Ordering};
+use std::collections::{HashMap, HashSet};
+
+/// A range of lines in a file.
+#[cfg_attr(feature = "io", derive(Deserialize, Serialize))]
+#[derive(Clone, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Location {
+    /// The name of the file.
+    pub name: String,
+    /// The first line of the range.
+    pub start: usize,
+    /// The last line of the range.
+    pub end: usize,
+}
+
+/// A block of code that is repeated, and the places where it appears.
+#[cfg_attr(feature = "io", derive(Deserialize, Serialize))]
+#[derive(Clone, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+pub struct CloneGroup {
+    /// The number of lines of code in the block.
+    pub lines: usize,
+    /// Where the block appears.
+    pub locations: Vec<Location>,
+}
+
+/// The repeated code of a language.
+#[cfg_attr(feature = "io", derive(Deserialize, Serialize))]
+#[derive(Clone, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Duplicates {
+    /// The number of lines of code.
+    pub code: usize,
+    /// The number of lines of code that are part of a repeated block.
+    pub duplicated: usize,
+    /// The repeated blocks, largest first.
+    pub groups: Vec<CloneGroup>,
+}
+
+impl Duplicates {
+    /// Finds the blocks of at least `min_lines` lines that are repeated, in
+    /// and across `files`. Each file is its name, and its normalised lines of
+    /// code along with the line numbers they're on.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let block = vec![("a".to_owned(), 1), ("b".to_owned(), 2), ("c".to_owned(), 3)];
+    /// let files = vec![("one.rs".to_owned(), block.clone()),
+    ///                  ("two.rs".to_owned(), block)];
+    ///
+    /// let duplicates = Duplicates::find(&files, 3);
+    ///
+    /// assert_eq!(duplicates.duplicated, 6);
+    /// assert_eq!(duplicates.groups[0].lines, 3);
+    /// ```
+    pub fn find(files: &[(String, Vec<(String, usize)>)], min_lines: usize) -> Self {
+        let min_lines = cmp::max(min_lines, 1);
+        let mut duplicates = Duplicates::default();
+        let mut windows: HashMap<Vec<&str>, Vec<(usize, usize)>> = HashMap::new();
+
+        for (file, (_, lines)) in files.iter().enumerate() {
+            duplicates.code += lines.len();
+
+            if lines.len() < min_lines {
+                continue;
+            }
+
+            for start in 0..lines.len() - min_lines + 1 {
+                let window = lines[start..start + min_lines].iter().map(|line| &*line.0).collect();
+                windows.entry(window).or_default().push((file, start));
+            }
+        }
+
+        // The places each repeated window appears, by each of those places.
+        let mut repeats = HashMap::new();
+        for occurrences in windows.values().filter(|occurrences| occurrences.len() > 1) {
+            for &occurrence in occurrences {
+                repeats.insert(occurrence, occurrences);
+            }
+        }
+
+        let mut duplicated = HashSet::new();
+        for &(file, start) in repeats.keys() {
+            for line in start..start + min_lines {
+                duplicated.insert((file, line));
+            }
+        }
+        duplicates.duplicated = duplicated.len();
+
+        for (&first, &occurrences) in &repeats {
+            // Each group is found from its first window, at its first place.
+            if first != occurrences[0] || continues(&repeats, occurrences) {
+                continue;
+            }
+
+            let mut length = min_lines;
+            let mut current = occurrences;
+            while let Some(&next) = repeats.get(&(first.0, first.1 + length - min_lines + 1)) {
+                if !follows(current, next) {
+                    break;
+                }
+                length += 1;
+                current = next;
+            }
+
+            let locations = occurrences.iter()
+                .map(|&(file, start)| {
+                    let (ref name, ref lines) = files[file];
+                    Location {
+                        name: name.clone(),
+                        start: lines[start].1,
+                        end: lines[start + length - 1].1,
+                    }
+                })
+                .collect();
+
+            duplicates.groups.push(CloneGroup {
+                lines: length,
+                locations,
+            });
+        }
+
+        duplicates.groups.sort_by(|a, b| match b.lines.cmp(&a.lines) {
+            Ordering::Equal => a.locations.cmp(&b.locations),
+            ordering => ordering,
+        });
+        duplicates
+    }
+
+    /// The percentage of the lines of code that are part of a repeated block.
+    pub fn percentage(&self) -> f64 {
+        if self.code == 0 {
+            0.0
+        } else {
+            self.duplicated as f64 * 100.0 / self.code as f64
+        }
+    }
+}
+
+/// Whether the window at `occurrences` continues a repeated window which
+/// starts a line earlier in each place.
+fn continues(repeats: &HashMap<(usize, usize), &Vec<(usize, usize)>>,
+             occurrences: &[(usize, usize)])
+             -> bool {
+    let (file, start) = occurrences[0];
+
+    if start == 0 {
+        return false;
+    }
+
+    match repeats.get(&(file, start - 1)) {
+        Some(previous) => follows(previous, occurrences),
+        None => false,
+    }
+}
+
+/// Whether each place of `next` is a line after the same place of `current`.
+fn follows(current: &[(usize, usize)], next: &[(usize, usize)]) -> bool {
+    current.len() == next.len() &&
+    current.iter().zip(next).all(|(&(file, start), &(next_file, next_start))| {
+        file == next_file && start + 1 == next_start
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use language::{Language, LanguageType, Languages};
+    use language::languages::code_lines;
+
+    #[test]
+    fn comments_and_whitespace() {
+        let c = Language::new_c().set_quotes(vec![("\"", "\"")]);
+        let definitions = Languages::generate_languages();
+        let first = code_lines("int a = 1;\nint b = 2; // two\nint c = 3;\n\nreturn a;",
+                               &c,
+                               &definitions);
+        let second = code_lines("/* copied */\nint a=1;\n  int b = 2; /* b */\nint c = 3;\nint d;",
+                                &c,
+                                &definitions);
+
+        let duplicates = Duplicates::find(&[("first.c".to_owned(), first),
+                                            ("second.c".to_owned(), second)],
+                                          3);
+
+        assert_eq!((8, 6), (duplicates.code, duplicates.duplicated));
+        assert_eq!(1, duplicates.groups.len());
+        assert_eq!(3, duplicates.groups[0].lines);
+        assert_eq!((1, 3), (duplicates.groups[0].locations[0].start,
+                            duplicates.groups[0].locations[0].end));
+        assert_eq!((2, 4), (duplicates.groups[0].locations[1].start,
+                            duplicates.groups[0].locations[1].end));
+    }
+
+    #[test]
+    fn comment_markers_in_strings() {
+        let c = Language::new_c().set_quotes(vec![("\"", "\"")]);
+        let definitions = Languages::generate_languages();
+        let first = code_lines("url = \"http://a\"; // a\nx = 1;\ny = 2;", &c, &definitions);
+        let second = code_lines("url = \"http://b\"; // b\nx = 1;\ny = 2;", &c, &definitions);
+
+        assert_eq!("url=\"http://a\";", first[0].0);
+
+        let duplicates = Duplicates::find(&[("first.c".to_owned(), first),
+                                            ("second.c".to_owned(), second)],
+                                          3);
+
+        assert_eq!(0, duplicates.duplicated);
+    }
+
+    #[test]
+    fn heredocs() {
+        let ruby = Languages::generate_languages().remove(&LanguageType::Ruby).unwrap();
+        let definitions = Languages::generate_languages();
+        let lines = code_lines("x = <<EOS\nit's \"\nEOS\ny = 1 # one", &ruby, &definitions);
+
+        assert_eq!(("y=1".to_owned(), 4), lines[3]);
+    }
+}
@@ -1,9 +1,82 @@
+use std::cmp;
+use std::collections::BTreeMap;
+use std::io::{self, Read};
 use std::path::PathBuf;
 use std::ops::AddAssign;
+use std::vec;
 
+use language::{languages, LanguageType};
 use sort::Sort;
 use sort::Sort::*;
-use stats::Stats;
+use stats::{self, Stats};
+
+/// How the end of a quote can appear inside of it without ending the quote.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Escape {
+    /// The end of the quote can't be escaped. ie. `r"\"` in Rust.
+    None,
+    /// A backslash escapes the next character. ie. `"\""`
+    Backslash,
+    /// The end of the quote is doubled. ie. `'It''s'` in SQL.
+    Doubled,
+    /// The given sequence escapes the next character. ie. `` "`"" `` in PowerShell.
+    Custom(&'static str),
+}
+
+impl From<&'static str> for Escape {
+    /// Converts the name of an escape style in `languages.json`, any other
+    /// string is the sequence of a custom escape.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// assert_eq!(Escape::from("doubled"), Escape::Doubled);
+    /// assert_eq!(Escape::from("`"), Escape::Custom("`"));
+    /// ```
+    fn from(escape: &'static str) -> Self {
+        match escape {
+            "none" => Escape::None,
+            "backslash" => Escape::Backslash,
+            "doubled" => Escape::Doubled,
+            custom => Escape::Custom(custom),
+        }
+    }
+}
+
+/// The tags that are counted as markers in comments by default.
+pub const DEFAULT_MARKER_TAGS: &'static [&'static str] = &["TODO", "FIXME", "HACK", "XXX", "BUG"];
+
+/// The kind of a single line.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LineKind {
+    /// A line that is empty, or only whitespace.
+    Blank,
+    /// A line of code.
+    Code,
+    /// A line that is only a comment.
+    Comment,
+    /// A line that is only a documentation comment.
+    Doc,
+    /// A line with both code and a comment.
+    Mixed,
+    /// A line of a preprocessor directive, which is also counted as code.
+    Preprocessor,
+    /// A line in a block disabled by the preprocessor, which is also counted
+    /// as a comment. ie. Inside `#if 0` in C.
+    Disabled,
+}
+
+/// How lines with both code and a comment are counted.
+#[derive(Clone, Copy, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+pub enum MixedPolicy {
+    /// The line is counted as code, the same as cloc.
+    #[default]
+    Code,
+    /// The line is counted as a comment.
+    Comment,
+    /// The line is counted as both code and a comment, so `code` and
+    /// `comments` can add up to more than `lines`.
+    Both,
+}
 
 /// Struct representing a single Language.
 #[cfg(feature = "io")]
@@ -15,6 +88,60 @@ pub struct Language {
     pub code: usize,
     /// Number of comments(both single, and multi-line)
     pub comments: usize,
+    /// Number of documentation comments. ie. `///` in Rust.
+    #[serde(default)]
+    pub doc_comments: usize,
+    /// Number of single line comments, excluding documentation.
+    #[serde(default)]
+    pub line_comments: usize,
+    /// Number of lines in multi line comments, excluding documentation.
+    #[serde(default)]
+    pub block_comments: usize,
+    /// Number of lines with both code and a comment. ie. `x = 5; // five`. These are counted in
+    /// `code` and `comments` according to `mixed_policy`.
+    #[serde(default)]
+    pub mixed: usize,
+    /// Number of lines of preprocessor directives. ie. `#include` in C. These are also counted
+    /// in `code`.
+    #[serde(default)]
+    pub preprocessor: usize,
+    /// Number of lines in blocks disabled by the preprocessor. ie. `#if 0` in C. These are also
+    /// counted in `comments`.
+    #[serde(default)]
+    pub disabled: usize,
+    /// Number of lines of code in tests. ie. Files in a `tests` directory, or `#[cfg(test)]`
+    /// modules in Rust. These are also counted in `code`.
+    #[serde(default)]
+    pub tests: usize,
+    /// The number of decisions in the code, an estimate of its cyclomatic complexity. ie.
+    /// `if` and `&&` in C.
+    #[serde(default)]
+    pub complexity: usize,
+    /// Number of logical lines of code, counted by the statement terminators and block openers
+    /// in the code. ie. `;` and `{` in C.
+    #[serde(default)]
+    pub logical: usize,
+    /// Number of marker tags in comments. ie. `TODO` and `FIXME`.
+    #[serde(default)]
+    pub markers: usize,
+    /// Number of bytes in the files.
+    #[serde(default)]
+    pub bytes: usize,
+    /// Number of characters in the files.
+    #[serde(default)]
+    pub chars: usize,
+    /// The length of the longest line in the files, in characters.
+    #[serde(default)]
+    pub max_line_length: usize,
+    /// The total length of the lines in the files, in characters, without line endings.
+    #[serde(default)]
+    pub line_length: usize,
+    /// The average length of the lines in the files, in characters, rounded down.
+    #[serde(default)]
+    pub avg_line_length: usize,
+    /// Totals of the languages embedded within the files. ie. JavaScript in HTML.
+    #[serde(default)]
+    pub children: BTreeMap<LanguageType, Stats>,
     /// A collection of files to be analysed.
     #[serde(skip_deserializing, skip_serializing)]
     pub files: Vec<PathBuf>,
@@ -25,18 +152,136 @@ pub struct Language {
     /// A collection of single line comments in the language. ie. `//` in Rust.
     #[serde(skip_deserializing, skip_serializing)]
     pub line_comment: Vec<&'static str>,
+    /// Line comments that are words, so they need to be followed by something other than
+    /// a letter, digit or `_`. ie. `REM` in Batch.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub keyword_comments: Vec<&'static str>,
+    /// Whether `keyword_comments` are matched regardless of case.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub case_insensitive: bool,
+    /// A collection of single line documentation comments. ie. `///` in Rust.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub doc_line_comment: Vec<&'static str>,
     /// A collection of tuples representing the start and end of multi line comments. ie. `/* comment */` in Rust.
     #[serde(skip_deserializing, skip_serializing)]
     pub multi_line: Vec<(&'static str, &'static str)>,
+    /// A collection of tuples representing the start and end of multi line documentation
+    /// comments. ie. `/** comment */` in Java.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub doc_multi_line: Vec<(&'static str, &'static str)>,
+    /// Line comments directly before a line starting with one of these are documentation.
+    /// ie. `func` in Go.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub doc_declarations: Vec<&'static str>,
+    /// Definitions whose body can start with a docstring, a string literal standing alone
+    /// as the first statement after a header ending in `:`. ie. `def` in Python. The start of a
+    /// file can also have a docstring if this isn't empty.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub docstrings: Vec<&'static str>,
+    /// Declarations that are documented by a string literal standing alone before them, with
+    /// only blank lines in between. ie. `function` in Julia.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub docstring_declarations: Vec<&'static str>,
+    /// Attributes that make the string literal following them documentation. ie. `@doc` in
+    /// Elixir.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub doc_attributes: Vec<&'static str>,
     /// Whether the language supports nested multi line comments or not.
     #[serde(skip_deserializing, skip_serializing)]
     pub nested: bool,
     /// A list of specific nested comments if this is empty all `multi_line` comments count.
     #[serde(skip_deserializing, skip_serializing)]
     pub nested_comments: Vec<(&'static str, &'static str)>,
-    /// A list of quotes by default it is `""`.
+    /// A list of tuples representing the start and end of block comments that only count
+    /// when they start a line. ie. `=begin` and `=end` in Ruby.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub line_blocks: Vec<(&'static str, &'static str)>,
+    /// The prefixes of preprocessor directives, which continue onto the next line when they end
+    /// with `\`. ie. `#` in C.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub directives: Vec<&'static str>,
+    /// The conditions of `#if` directives that are never true, the lines up to the matching
+    /// `#else`, `#elif` or `#endif` are disabled. ie. `0` in C.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub disabled_conditions: Vec<&'static str>,
+    /// Whether the blocks disabled by `disabled_conditions` are counted as comments, by default
+    /// they are counted as code.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub disable_blocks: bool,
+    /// Patterns of the names of files that are tests, where `*` matches any text. ie.
+    /// `*_test.go` in Go.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub test_files: Vec<&'static str>,
+    /// Attributes that mark the item after them as a test, up to the end of its block.
+    /// ie. `#[cfg(test)]` in Rust.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub test_attributes: Vec<&'static str>,
+    /// The keywords and operators that are decisions, which are counted in `complexity`.
+    /// ie. `if`, `while` and `&&` in C.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub decisions: Vec<&'static str>,
+    /// The keywords that start a block of branches, where the first decision after them isn't
+    /// counted, as n branches only make n - 1 decisions. ie. `match` in Rust.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub decision_blocks: Vec<&'static str>,
+    /// The tokens that end a logical line of code. `"\n"` ends a logical line at the end of a
+    /// line, unless it's continued by a `\` or by open brackets. ie. `;`, `{` and `}` in C.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub terminators: Vec<&'static str>,
+    /// A list of quotes and how they're escaped, by default it is `""` escaped by a backslash.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub quotes: Vec<(&'static str, &'static str, Escape)>,
+    /// A list of quotes around a single character, these are only quotes if they're closed.
+    /// ie. `'a'` but not the lifetime `'a` in Rust.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub char_quotes: Vec<(&'static str, &'static str)>,
+    /// A list of quotes with a delimiter chosen by the string, as the prefix, the opening and
+    /// closing brackets, and the suffix. ie. `R"delimiter( )delimiter"` in C++.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub delimited_quotes: Vec<(&'static str, &'static str, &'static str, &'static str)>,
+    /// The operators that start a heredoc, which ends at a line that is only the identifier
+    /// following the operator. ie. `<<EOF` in Bash.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub heredocs: Vec<&'static str>,
+    /// Comments that only start in `comment_column`, and aren't comments anywhere else.
+    /// ie. `C` in the first column of fixed form FORTRAN.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub column_comments: Vec<&'static str>,
+    /// The column in which `column_comments` start, counting from 1.
     #[serde(skip_deserializing, skip_serializing)]
-    pub quotes: Vec<(&'static str, &'static str)>,
+    pub comment_column: usize,
+    /// The column that marks a line as continuing the previous one when it isn't blank
+    /// or `0`, the columns up to and including it are skipped on those lines. ie. 6 in
+    /// fixed form FORTRAN.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub continuation_column: Option<usize>,
+    /// The character in `continuation_column` that continues a line. If there isn't one, any
+    /// character other than a space or `0` does. ie. `-` in COBOL.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub continuation_mark: Option<char>,
+    /// The last column that is read, any text after it is ignored. ie. 72 in fixed form
+    /// FORTRAN, where the columns after it were used for sequence numbers.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub last_column: Option<usize>,
+    /// The number of columns at the start of each line that hold sequence numbers, a line with
+    /// only a sequence number is blank. ie. 6 in COBOL.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub sequence_columns: usize,
+    /// How lines with both code and a comment are counted in `code` and `comments`.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub mixed_policy: MixedPolicy,
+    /// The tags that are counted as markers in comments, when they're whole words.
+    /// The definitions of `Languages::generate_languages` use `DEFAULT_MARKER_TAGS`.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub marker_tags: Vec<String>,
+    /// A list of tuples representing the start and end of blocks of another language embedded
+    /// in this one, and what that language is. ie. `<script>` blocks in HTML.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub embedded: Vec<(&'static str, &'static str, LanguageType)>,
+    /// A list of fences that start and end code blocks in prose languages. ie. `` ``` `` in
+    /// Markdown. If this isn't empty, text outside of code blocks is counted as comments.
+    #[serde(skip_deserializing, skip_serializing)]
+    pub code_fences: Vec<&'static str>,
 }
 
 #[cfg(not(feature = "io"))]
@@ -48,6 +293,44 @@ pub struct Language {
     pub code: usize,
     /// Number of comments(both single, and multi-line)
     pub comments: usize,
+    /// Number of documentation comments. ie. `///` in Rust.
+    pub doc_comments: usize,
+    /// Number of single line comments, excluding documentation.
+    pub line_comments: usize,
+    /// Number of lines in multi line comments, excluding documentation.
+    pub block_comments: usize,
+    /// Number of lines with both code and a comment. ie. `x = 5; // five`. These are counted in
+    /// `code` and `comments` according to `mixed_policy`.
+    pub mixed: usize,
+    /// Number of lines of preprocessor directives. ie. `#include` in C. These are also counted
+    /// in `code`.
+    pub preprocessor: usize,
+    /// Number of lines in blocks disabled by the preprocessor. ie. `#if 0` in C. These are also
+    /// counted in `comments`.
+    pub disabled: usize,
+    /// Number of lines of code in tests. ie. Files in a `tests` directory, or `#[cfg(test)]`
+    /// modules in Rust. These are also counted in `code`.
+    pub tests: usize,
+    /// The number of decisions in the code, an estimate of its cyclomatic complexity. ie.
+    /// `if` and `&&` in C.
+    pub complexity: usize,
+    /// Number of logical lines of code, counted by the statement terminators and block openers
+    /// in the code. ie. `;` and `{` in C.
+    pub logical: usize,
+    /// Number of marker tags in comments. ie. `TODO` and `FIXME`.
+    pub markers: usize,
+    /// Number of bytes in the files.
+    pub bytes: usize,
+    /// Number of characters in the files.
+    pub chars: usize,
+    /// The length of the longest line in the files, in characters.
+    pub max_line_length: usize,
+    /// The total length of the lines in the files, in characters, without line endings.
+    pub line_length: usize,
+    /// The average length of the lines in the files, in characters, rounded down.
+    pub avg_line_length: usize,
+    /// Totals of the languages embedded within the files. ie. JavaScript in HTML.
+    pub children: BTreeMap<LanguageType, Stats>,
     /// A collection of files to be analysed.
     pub files: Vec<PathBuf>,
     /// A collection of statistics based on the files provide from `files`
@@ -56,14 +339,102 @@ pub struct Language {
     pub lines: usize,
     /// A collection of single line comments in the language. ie. `//` in Rust.
     pub line_comment: Vec<&'static str>,
+    /// Line comments that are words, so they need to be followed by something other than
+    /// a letter, digit or `_`. ie. `REM` in Batch.
+    pub keyword_comments: Vec<&'static str>,
+    /// Whether `keyword_comments` are matched regardless of case.
+    pub case_insensitive: bool,
+    /// A collection of single line documentation comments. ie. `///` in Rust.
+    pub doc_line_comment: Vec<&'static str>,
     /// A collection of tuples representing the start and end of multi line comments. ie. `/* comment */` in Rust.
     pub multi_line: Vec<(&'static str, &'static str)>,
+    /// A collection of tuples representing the start and end of multi line documentation
+    /// comments. ie. `/** comment */` in Java.
+    pub doc_multi_line: Vec<(&'static str, &'static str)>,
+    /// Line comments directly before a line starting with one of these are documentation.
+    /// ie. `func` in Go.
+    pub doc_declarations: Vec<&'static str>,
+    /// Definitions whose body can start with a docstring, a string literal standing alone
+    /// as the first statement after a header ending in `:`. ie. `def` in Python. The start of a
+    /// file can also have a docstring if this isn't empty.
+    pub docstrings: Vec<&'static str>,
+    /// Declarations that are documented by a string literal standing alone before them, with
+    /// only blank lines in between. ie. `function` in Julia.
+    pub docstring_declarations: Vec<&'static str>,
+    /// Attributes that make the string literal following them documentation. ie. `@doc` in
+    /// Elixir.
+    pub doc_attributes: Vec<&'static str>,
     /// Whether the language supports nested multi line comments or not.
     pub nested: bool,
     /// A list of specific nested comments if this is empty all `multi_line` comments count.
     pub nested_comments: Vec<(&'static str, &'static str)>,
-    /// A list of quotes by default it is `""`.
-    pub quotes: Vec<(&'static str, &'static str)>,
+    /// A list of tuples representing the start and end of block comments that only count
+    /// when they start a line. ie. `=begin` and `=end` in Ruby.
+    pub line_blocks: Vec<(&'static str, &'static str)>,
+    /// The prefixes of preprocessor directives, which continue onto the next line when they end
+    /// with `\`. ie. `#` in C.
+    pub directives: Vec<&'static str>,
+    /// The conditions of `#if` directives that are never true, the lines up to the matching
+    /// `#else`, `#elif` or `#endif` are disabled. ie. `0` in C.
+    pub disabled_conditions: Vec<&'static str>,
+    /// Whether the blocks disabled by `disabled_conditions` are counted as comments, by default
+    /// they are counted as code.
+    pub disable_blocks: bool,
+    /// Patterns of the names of files that are tests, where `*` matches any text. ie.
+    /// `*_test.go` in Go.
+    pub test_files: Vec<&'static str>,
+    /// Attributes that mark the item after them as a test, up to the end of its block.
+    /// ie. `#[cfg(test)]` in Rust.
+    pub test_attributes: Vec<&'static str>,
+    /// The keywords and operators that are decisions, which are counted in `complexity`.
+    /// ie. `if`, `while` and `&&` in C.
+    pub decisions: Vec<&'static str>,
+    /// The keywords that start a block of branches, where the first decision after them isn't
+    /// counted, as n branches only make n - 1 decisions. ie. `match` in Rust.
+    pub decision_blocks: Vec<&'static str>,
+    /// The tokens that end a logical line of code. `"\n"` ends a logical line at the end of a
+    /// line, unless it's continued by a `\` or by open brackets. ie. `;`, `{` and `}` in C.
+    pub terminators: Vec<&'static str>,
+    /// A list of quotes and how they're escaped, by default it is `""` escaped by a backslash.
+    pub quotes: Vec<(&'static str, &'static str, Escape)>,
+    /// A list of quotes around a single character, these are only quotes if they're closed.
+    /// ie. `'a'` but not the lifetime `'a` in Rust.
+    pub char_quotes: Vec<(&'static str, &'static str)>,
+    /// A list of quotes with a delimiter chosen by the string, as the prefix, the opening and
+    /// closing brackets, and the suffix. ie. `R"delimiter( )delimiter"` in C++.
+    pub delimited_quotes: Vec<(&'static str, &'static str, &'static str, &'static str)>,
+    /// The operators that start a heredoc, which ends at a line that is only the identifier
+    /// following the operator. ie. `<<EOF` in Bash.
+    pub heredocs: Vec<&'static str>,
+    /// Comments that only start in `comment_column`, and aren't comments anywhere else.
+    /// ie. `C` in the first column of fixed form FORTRAN.
+    pub column_comments: Vec<&'static str>,
+    /// The column in which `column_comments` start, counting from 1.
+    pub comment_column: usize,
+    /// The column that marks a line as continuing the previous one when it isn't blank
+    /// or `0`, the columns up to and including it are skipped on those lines. ie. 6 in
+    /// fixed form FORTRAN.
+    pub continuation_column: Option<usize>,
+    /// The character in `continuation_column` that continues a line. If there isn't one, any
+    /// character other than a space or `0` does. ie. `-` in COBOL.
+    pub continuation_mark: Option<char>,
+    /// The last column that is read, any text after it is ignored. ie. 72 in fixed form
+    /// FORTRAN, where the columns after it were used for sequence numbers.
+    pub last_column: Option<usize>,
+    /// The number of columns at the start of each line that hold sequence numbers, a line with
+    /// only a sequence number is blank. ie. 6 in COBOL.
+    pub sequence_columns: usize,
+    /// How lines with both code and a comment are counted in `code` and `comments`.
+    pub mixed_policy: MixedPolicy,
+    /// The tags that are counted as markers in comments, when they're whole words.
+    /// The definitions of `Languages::generate_languages` use `DEFAULT_MARKER_TAGS`.
+    pub marker_tags: Vec<String>,
+    /// A list of tuples representing the start and end of blocks of another language embedded
+    /// in this one, and what that language is. ie. `<script>` blocks in HTML.
+    pub embedded: Vec<(&'static str, &'static str, LanguageType)>,
+    /// A list of fences that start and end code blocks in prose languages. ie. `` ``` `` in
+    /// Markdown. If this isn't empty, text outside of code blocks is counted as comments.
+    pub code_fences: Vec<&'static str>,
 }
 
 
@@ -111,7 +482,7 @@ impl Language {
         Language {
             line_comment: vec!["//"],
             multi_line: vec![("/*", "*/")],
-            quotes: vec![("\"", "\"")],
+            quotes: vec![("\"", "\"", Escape::Backslash)],
             ..Self::default()
         }
     }
@@ -129,7 +500,7 @@ impl Language {
     pub fn new_func() -> Self {
         Language {
             multi_line: vec![("(*", "*)")],
-            quotes: vec![("\"", "\"")],
+            quotes: vec![("\"", "\"", Escape::Backslash)],
             ..Self::default()
         }
     }
@@ -147,7 +518,7 @@ impl Language {
     pub fn new_html() -> Self {
         Language {
             multi_line: vec![("<!--", "-->")],
-            quotes: vec![("\"", "\"")],
+            quotes: vec![("\"", "\"", Escape::Backslash)],
             ..Self::default()
         }
     }
@@ -190,7 +561,7 @@ impl Language {
     pub fn new_multi(multi_line: Vec<(&'static str, &'static str)>) -> Self {
         Language {
             multi_line: multi_line,
-            quotes: vec![("\"", "\"")],
+            quotes: vec![("\"", "\"", Escape::Backslash)],
             ..Self::default()
         }
     }
@@ -209,7 +580,7 @@ impl Language {
         Language {
             line_comment: vec!["%"],
             multi_line: vec![("/*", "*/")],
-            quotes: vec![("\"", "\"")],
+            quotes: vec![("\"", "\"", Escape::Backslash)],
             ..Self::default()
         }
     }
@@ -223,7 +594,7 @@ impl Language {
     pub fn new_single(line_comment: Vec<&'static str>) -> Self {
         Language {
             line_comment: line_comment,
-            quotes: vec![("\"", "\"")],
+            quotes: vec![("\"", "\"", Escape::Backslash)],
             ..Self::default()
         }
     }
@@ -237,7 +608,8 @@ impl Language {
     /// assert!(rust.is_empty());
     /// ```
     pub fn is_empty(&self) -> bool {
-        self.code == 0 && self.comments == 0 && self.blanks == 0 && self.lines == 0
+        self.code == 0 && self.comments == 0 && self.blanks == 0 && self.lines == 0 &&
+        self.children.is_empty()
     }
 
     /// Checks if the language doesn't contain any comments.
@@ -249,7 +621,36 @@ impl Language {
     /// assert!(json.is_blank());
     /// ```
     pub fn is_blank(&self) -> bool {
-        self.line_comment.is_empty() && self.multi_line.is_empty()
+        self.line_comment.is_empty() && self.keyword_comments.is_empty() &&
+        self.multi_line.is_empty() && self.line_blocks.is_empty() && self.code_fences.is_empty()
+    }
+
+    /// Whether `text` starts with one of the language's keyword comments.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let batch = Language::new_single(vec!["::"])
+    ///                 .keyword_comments(vec!["REM"])
+    ///                 .case_insensitive();
+    /// assert!(batch.starts_with_keyword_comment("rem comment"));
+    /// assert!(batch.starts_with_keyword_comment("REM"));
+    /// assert!(!batch.starts_with_keyword_comment("REMOVE_ITEM x"));
+    /// ```
+    pub fn starts_with_keyword_comment(&self, text: &str) -> bool {
+        self.keyword_comments.iter().any(|keyword| {
+            let length = keyword.len();
+            if text.len() < length || !text.is_char_boundary(length) {
+                return false;
+            }
+
+            let matches = if self.case_insensitive {
+                text[..length].to_lowercase() == keyword.to_lowercase()
+            } else {
+                &text[..length] == *keyword
+            };
+
+            matches && text[length..].chars().next().is_none_or(|c| !is_word(c))
+        })
     }
 
     /// Specify if the the language supports nested multi line comments.
@@ -264,6 +665,34 @@ impl Language {
         self
     }
 
+    /// Adds line comments that are words, which aren't comments when they're
+    /// the start of a longer word. ie. `dnl` in Autoconf.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let autoconf = Language::new_hash().keyword_comments(vec!["dnl"]);
+    /// assert_eq!(autoconf.keyword_comments, vec!["dnl"]);
+    /// ```
+    pub fn keyword_comments(mut self, keyword_comments: Vec<&'static str>) -> Self {
+        self.keyword_comments = keyword_comments;
+        self
+    }
+
+    /// Specify that the language's keyword comments are matched regardless of
+    /// case. ie. `REM`, `Rem` and `rem` in Batch.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let batch = Language::new_single(vec!["::"])
+    ///                 .keyword_comments(vec!["REM"])
+    ///                 .case_insensitive();
+    /// assert!(batch.case_insensitive);
+    /// ```
+    pub fn case_insensitive(mut self) -> Self {
+        self.case_insensitive = true;
+        self
+    }
+
     /// Specify if the the language supports nested multi line comments.
     /// And which are nested. If this is specified there is no need to
     /// call the `nested` function.
@@ -281,6 +710,122 @@ impl Language {
         self
     }
 
+    /// Adds the prefixes of preprocessor directives.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let c = Language::new_c().directives(vec!["#"]);
+    /// assert_eq!(c.directives, vec!["#"]);
+    /// ```
+    pub fn directives(mut self, directives: Vec<&'static str>) -> Self {
+        self.directives = directives;
+        self
+    }
+
+    /// Adds the conditions of `#if` directives that disable the block after
+    /// them.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let c = Language::new_c().directives(vec!["#"]).disabled_conditions(vec!["0"]);
+    /// assert_eq!(c.disabled_conditions, vec!["0"]);
+    /// ```
+    pub fn disabled_conditions(mut self, disabled_conditions: Vec<&'static str>) -> Self {
+        self.disabled_conditions = disabled_conditions;
+        self
+    }
+
+    /// Specifies whether the blocks disabled by `disabled_conditions` are
+    /// counted as comments.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let c = Language::new_c().disabled_conditions(vec!["0"]).disable_blocks(true);
+    /// assert!(c.disable_blocks);
+    /// ```
+    pub fn disable_blocks(mut self, disable_blocks: bool) -> Self {
+        self.disable_blocks = disable_blocks;
+        self
+    }
+
+    /// Adds patterns of the names of files that are tests, where `*` matches
+    /// any text.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let go = Language::new_c().test_files(vec!["*_test.go"]);
+    /// assert_eq!(go.test_files, vec!["*_test.go"]);
+    /// ```
+    pub fn test_files(mut self, test_files: Vec<&'static str>) -> Self {
+        self.test_files = test_files;
+        self
+    }
+
+    /// Adds attributes that mark the item after them as a test.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let rust = Language::new_c().test_attributes(vec!["#[cfg(test)]"]);
+    /// assert_eq!(rust.test_attributes, vec!["#[cfg(test)]"]);
+    /// ```
+    pub fn test_attributes(mut self, test_attributes: Vec<&'static str>) -> Self {
+        self.test_attributes = test_attributes;
+        self
+    }
+
+    /// Adds the keywords and operators that are decisions, keywords are only
+    /// counted when they're whole words.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let c = Language::new_c().decisions(vec!["if", "while", "&&", "||"]);
+    /// assert_eq!(c.decisions, vec!["if", "while", "&&", "||"]);
+    /// ```
+    pub fn decisions(mut self, decisions: Vec<&'static str>) -> Self {
+        self.decisions = decisions;
+        self
+    }
+
+    /// Adds the keywords that start a block of branches, the first decision
+    /// after them isn't counted. Keywords are only counted when they're whole
+    /// words.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let rust = Language::new_c().decisions(vec!["=>"]).decision_blocks(vec!["match"]);
+    /// assert_eq!(rust.decision_blocks, vec!["match"]);
+    /// ```
+    pub fn decision_blocks(mut self, decision_blocks: Vec<&'static str>) -> Self {
+        self.decision_blocks = decision_blocks;
+        self
+    }
+
+    /// Adds the tokens that end a logical line of code.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let c = Language::new_c().terminators(vec![";", "{", "}"]);
+    /// assert_eq!(c.terminators, vec![";", "{", "}"]);
+    /// ```
+    pub fn terminators(mut self, terminators: Vec<&'static str>) -> Self {
+        self.terminators = terminators;
+        self
+    }
+
+    /// Adds block comments whose start and end only count at the start of a
+    /// line, and when they're followed by something other than a letter,
+    /// digit or `_`. ie. `=begin` and `=end` in Ruby.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let ruby = Language::new_hash().line_blocks(vec![("=begin", "=end")]);
+    /// assert_eq!(ruby.line_blocks, vec![("=begin", "=end")]);
+    /// ```
+    pub fn line_blocks(mut self, line_blocks: Vec<(&'static str, &'static str)>) -> Self {
+        self.line_blocks = line_blocks;
+        self
+    }
+
     /// Specifies if the language has a quotes to define a string where
     /// the commenting syntax would be ignored. By default it is only
     /// `""` quotes that are ignored.
@@ -292,10 +837,318 @@ impl Language {
     /// assert!(!javascript.quotes.is_empty());
     /// ```
     pub fn set_quotes(mut self, quotes: Vec<(&'static str, &'static str)>) -> Self {
+        self.quotes = quotes.into_iter()
+            .map(|(start, end)| (start, end, Escape::Backslash))
+            .collect();
+        self
+    }
+
+    /// Specifies the quotes of the language along with how each of them is
+    /// escaped.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let sql = Language::new(vec!["--"], vec![("/*", "*/")])
+    ///               .escaped_quotes(vec![("'", "'", Escape::Doubled)]);
+    /// assert_eq!(sql.quotes, vec![("'", "'", Escape::Doubled)]);
+    /// ```
+    pub fn escaped_quotes(mut self, quotes: Vec<(&'static str, &'static str, Escape)>) -> Self {
         self.quotes = quotes;
         self
     }
 
+    /// Specifies quotes around a single character. These only count as quotes
+    /// when they are closed, so that Rust's lifetimes aren't mistaken for the
+    /// start of a quote.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let rust = Language::new_c().char_quotes(vec![("'", "'")]);
+    /// assert_eq!(rust.char_quotes, vec![("'", "'")]);
+    /// ```
+    pub fn char_quotes(mut self, char_quotes: Vec<(&'static str, &'static str)>) -> Self {
+        self.char_quotes = char_quotes;
+        self
+    }
+
+    /// Specifies quotes that end with a delimiter chosen by the string, as the
+    /// prefix, the opening and closing brackets, and the suffix. ie. C++'s
+    /// `R"sql(...)sql"` is `("R\"", "(", ")", "\"")`.
+    pub fn delimited_quotes(mut self,
+                            delimited_quotes: Vec<(&'static str,
+                                                   &'static str,
+                                                   &'static str,
+                                                   &'static str)>)
+                            -> Self {
+        self.delimited_quotes = delimited_quotes;
+        self
+    }
+
+    /// Specifies blocks of other languages that can be embedded in this one.
+    /// Lines between the start and end of the block are counted using the
+    /// embedded language's comments, and are added to `children` rather
+    /// than this language.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let html = Language::new_html()
+    ///                 .embedded(vec![("<script", "</script>", LanguageType::JavaScript)]);
+    /// assert_eq!(html.embedded[0].2, LanguageType::JavaScript);
+    /// ```
+    pub fn embedded(mut self,
+                    embedded: Vec<(&'static str, &'static str, LanguageType)>)
+                    -> Self {
+        self.embedded = embedded;
+        self
+    }
+
+    /// Specifies the fences of code blocks, making this a prose language.
+    /// Code blocks with a language in their info string are counted as that
+    /// language, and the text outside of code blocks is counted as comments.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let markdown = Language::new_blank().code_fences(vec!["```", "~~~"]);
+    /// assert!(!markdown.is_blank());
+    /// ```
+    pub fn code_fences(mut self, code_fences: Vec<&'static str>) -> Self {
+        self.code_fences = code_fences;
+        self
+    }
+
+    /// Specifies the documentation comments of the language. Single line
+    /// documentation comments are checked before `line_comment`, so `///`
+    /// isn't counted as a `//` comment.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let rust = Language::new_c()
+    ///                 .doc_line_comment(vec!["///", "//!"])
+    ///                 .doc_multi_line(vec![("/**", "*/"), ("/*!", "*/")]);
+    /// assert_eq!(rust.doc_line_comment, vec!["///", "//!"]);
+    /// ```
+    pub fn doc_line_comment(mut self, doc_line_comment: Vec<&'static str>) -> Self {
+        self.doc_line_comment = doc_line_comment;
+        self
+    }
+
+    /// Specifies the multi line documentation comments of the language.
+    /// ie. `/** comment */` in Java.
+    pub fn doc_multi_line(mut self, doc_multi_line: Vec<(&'static str, &'static str)>) -> Self {
+        self.doc_multi_line = doc_multi_line;
+        self
+    }
+
+    /// Specifies the declarations whose directly preceding line comments are
+    /// documentation, for languages such as Go that don't have separate
+    /// documentation comments.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let go = Language::new_c().doc_declarations(vec!["func ", "type "]);
+    /// assert_eq!(go.doc_declarations, vec!["func ", "type "]);
+    /// ```
+    pub fn doc_declarations(mut self, doc_declarations: Vec<&'static str>) -> Self {
+        self.doc_declarations = doc_declarations;
+        self
+    }
+
+    /// Specifies the definitions whose body can start with a docstring.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let python = Language::new_hash().docstrings(vec!["def ", "class "]);
+    /// assert_eq!(python.docstrings, vec!["def ", "class "]);
+    /// ```
+    pub fn docstrings(mut self, docstrings: Vec<&'static str>) -> Self {
+        self.docstrings = docstrings;
+        self
+    }
+
+    /// Specifies the declarations documented by a docstring before them.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let julia = Language::new_hash().docstring_declarations(vec!["function "]);
+    /// assert_eq!(julia.docstring_declarations, vec!["function "]);
+    /// ```
+    pub fn docstring_declarations(mut self, declarations: Vec<&'static str>) -> Self {
+        self.docstring_declarations = declarations;
+        self
+    }
+
+    /// Specifies the attributes that make the string literal following them
+    /// documentation. ie. `@moduledoc` in Elixir.
+    pub fn doc_attributes(mut self, doc_attributes: Vec<&'static str>) -> Self {
+        self.doc_attributes = doc_attributes;
+        self
+    }
+
+    /// Specifies the operators that start a heredoc. The heredoc ends at a
+    /// line that is only the identifier following the operator, and the lines
+    /// in between are counted as code.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let ruby = Language::new_hash().heredocs(vec!["<<~", "<<-", "<<"]);
+    /// assert_eq!(ruby.heredocs, vec!["<<~", "<<-", "<<"]);
+    /// ```
+    pub fn heredocs(mut self, heredocs: Vec<&'static str>) -> Self {
+        self.heredocs = heredocs;
+        self
+    }
+
+    /// Adds comments that only start in the given column, counting from 1.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let fortran = Language::new_single(vec!["!"]).column_comments(1, vec!["C", "*"]);
+    /// assert_eq!(fortran.comment_column, 1);
+    /// assert_eq!(fortran.column_comments, vec!["C", "*"]);
+    /// ```
+    pub fn column_comments(mut self, column: usize, comments: Vec<&'static str>) -> Self {
+        self.comment_column = column;
+        self.column_comments = comments;
+        self
+    }
+
+    /// Sets the column that marks a line as a continuation of the previous line.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let fortran = Language::new_single(vec!["!"]).continuation_column(6);
+    /// assert_eq!(fortran.continuation_column, Some(6));
+    /// ```
+    pub fn continuation_column(mut self, column: usize) -> Self {
+        self.continuation_column = Some(column);
+        self
+    }
+
+    /// Sets the character in the continuation column that continues a line.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let cobol = Language::new_single(vec!["*>"]).continuation_column(7).continuation_mark('-');
+    /// assert_eq!(cobol.continuation_mark, Some('-'));
+    /// ```
+    pub fn continuation_mark(mut self, mark: char) -> Self {
+        self.continuation_mark = Some(mark);
+        self
+    }
+
+    /// Sets the last column that is read, any text after it is ignored.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let fortran = Language::new_single(vec!["!"]).last_column(72);
+    /// assert_eq!(fortran.last_column, Some(72));
+    /// ```
+    pub fn last_column(mut self, column: usize) -> Self {
+        self.last_column = Some(column);
+        self
+    }
+
+    /// Sets the number of columns at the start of each line that hold
+    /// sequence numbers.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let cobol = Language::new_single(vec!["*>"]).sequence_columns(6);
+    /// assert_eq!(cobol.sequence_columns, 6);
+    /// ```
+    pub fn sequence_columns(mut self, columns: usize) -> Self {
+        self.sequence_columns = columns;
+        self
+    }
+
+    /// Sets the tags that are counted as markers in comments.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let c = Language::new_c().marker_tags(vec![String::from("TODO")]);
+    /// assert_eq!(c.marker_tags, vec!["TODO"]);
+    /// ```
+    pub fn marker_tags(mut self, marker_tags: Vec<String>) -> Self {
+        self.marker_tags = marker_tags;
+        self
+    }
+
+    /// Specifies how lines with both code and a comment are counted.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let rust = Language::new_c().mixed_policy(MixedPolicy::Both);
+    /// assert_eq!(rust.mixed_policy, MixedPolicy::Both);
+    /// ```
+    pub fn mixed_policy(mut self, mixed_policy: MixedPolicy) -> Self {
+        self.mixed_policy = mixed_policy;
+        self
+    }
+
+    /// Counts the lines of `text` without adding them to the language, the
+    /// same way as when counting files. Languages with special rules, such as
+    /// Jupyter notebooks, are counted with `LanguageType::count_str`.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let rust = Language::new_c();
+    /// let stats = rust.count_str("// comment\nfn main() {}\n\n");
+    ///
+    /// assert_eq!((1, 1, 1), (stats.code, stats.comments, stats.blanks));
+    /// ```
+    pub fn count_str(&self, text: &str) -> Stats {
+        let mut stats = Stats::default();
+        languages::with_definitions(|definitions| {
+            languages::count_stats(text, text.len(), None, self, definitions, &mut stats)
+        });
+        stats
+    }
+
+    /// Reads all of `reader` and counts its lines the same way as
+    /// `count_str`, any invalid UTF-8 is replaced.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let rust = Language::new_c();
+    /// let stats = rust.count_reader(&b"/* comment */\nfn main() {}\xff"[..]).unwrap();
+    ///
+    /// assert_eq!((1, 1), (stats.code, stats.comments));
+    /// assert_eq!(27, stats.bytes);
+    /// ```
+    pub fn count_reader<R: Read>(&self, mut reader: R) -> io::Result<Stats> {
+        let mut contents = Vec::new();
+        try!(reader.read_to_end(&mut contents));
+
+        let mut stats = Stats::default();
+        languages::with_definitions(|definitions| {
+            languages::count_stats(&languages::decode(&contents),
+                                   contents.len(),
+                                   None,
+                                   self,
+                                   definitions,
+                                   &mut stats)
+        });
+        Ok(stats)
+    }
+
+    /// Classifies each line of `text` the same way as when counting files,
+    /// returning the number of each line, starting at 1, with its kind. The
+    /// whole of `text` is classified before the first line is returned, as
+    /// lines can turn out to be documentation because of the lines after them.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let rust = Language::new_c();
+    /// let lines: Vec<_> = rust.classify("// comment\nlet x = 5; // five\n\n").collect();
+    ///
+    /// assert_eq!(lines,
+    ///            vec![(1, LineKind::Comment), (2, LineKind::Mixed), (3, LineKind::Blank)]);
+    /// ```
+    pub fn classify(&self, text: &str) -> vec::IntoIter<(usize, LineKind)> {
+        languages::with_definitions(|definitions| languages::classify(text, self, definitions))
+            .into_iter()
+    }
+
     /// Sorts each of the `Stats` structs contained in the language based
     /// on what category is provided
     /// panic!'s if given the wrong category.
@@ -322,21 +1175,41 @@ impl Language {
     pub fn sort_by(&mut self, category: Sort) {
         match category {
             Blanks => self.stats.sort_by(|a, b| b.blanks.cmp(&a.blanks)),
+            Bytes => self.stats.sort_by(|a, b| b.bytes.cmp(&a.bytes)),
             Comments => self.stats.sort_by(|a, b| b.comments.cmp(&a.comments)),
             Code => self.stats.sort_by(|a, b| b.code.cmp(&a.code)),
+            Complexity => self.stats.sort_by(|a, b| b.complexity.cmp(&a.complexity)),
             Files => self.stats.sort_by(|a, b| a.name.cmp(&b.name)),
             Lines => self.stats.sort_by(|a, b| b.lines.cmp(&a.lines)),
         }
     }
 }
 
+/// Whether the character can be part of a word, for the boundaries of
+/// keywords.
+pub fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl Language {
+    /// The number of lines of the files, including the languages embedded
+    /// within them.
+    fn total_lines(&self) -> usize {
+        self.children.values().fold(self.lines, |lines, child| lines + child.lines)
+    }
+
+    fn add_children(&mut self, children: &BTreeMap<LanguageType, Stats>) {
+        for (language_type, child) in children {
+            *self.children
+                .entry(*language_type)
+                .or_insert_with(|| Stats::new(language_type.name())) += child;
+        }
+    }
+}
+
 impl AddAssign for Language {
     fn add_assign(&mut self, rhs: Self) {
-        self.lines += rhs.lines;
-        self.comments += rhs.comments;
-        self.blanks += rhs.blanks;
-        self.code += rhs.code;
-        self.stats.extend_from_slice(&*rhs.stats);
+        *self += &rhs;
     }
 }
 
@@ -344,19 +1217,31 @@ impl<'a> AddAssign<&'a Language> for Language {
     fn add_assign(&mut self, rhs: &'a Self) {
         self.lines += rhs.lines;
         self.comments += rhs.comments;
+        self.doc_comments += rhs.doc_comments;
+        self.line_comments += rhs.line_comments;
+        self.block_comments += rhs.block_comments;
+        self.mixed += rhs.mixed;
+        self.preprocessor += rhs.preprocessor;
+        self.disabled += rhs.disabled;
+        self.tests += rhs.tests;
+        self.complexity += rhs.complexity;
+        self.logical += rhs.logical;
+        self.markers += rhs.markers;
+        self.bytes += rhs.bytes;
+        self.chars += rhs.chars;
+        self.max_line_length = cmp::max(self.max_line_length, rhs.max_line_length);
+        self.line_length += rhs.line_length;
         self.blanks += rhs.blanks;
         self.code += rhs.code;
+        self.add_children(&rhs.children);
+        self.avg_line_length = stats::average_line_length(self.line_length, self.total_lines());
         self.stats.extend_from_slice(&*rhs.stats);
     }
 }
 
 impl<'a> AddAssign<&'a mut Language> for Language {
     fn add_assign(&mut self, rhs: &mut Self) {
-        self.lines += rhs.lines;
-        self.comments += rhs.comments;
-        self.blanks += rhs.blanks;
-        self.code += rhs.code;
-        self.stats.extend_from_slice(&*rhs.stats);
+        *self += &*rhs;
     }
 }
 
@@ -365,7 +1250,23 @@ impl AddAssign<Stats> for Language {
         self.lines += rhs.lines;
         self.code += rhs.code;
         self.comments += rhs.comments;
+        self.doc_comments += rhs.doc_comments;
+        self.line_comments += rhs.line_comments;
+        self.block_comments += rhs.block_comments;
+        self.mixed += rhs.mixed;
+        self.preprocessor += rhs.preprocessor;
+        self.disabled += rhs.disabled;
+        self.tests += rhs.tests;
+        self.complexity += rhs.complexity;
+        self.logical += rhs.logical;
+        self.markers += rhs.markers.len();
+        self.bytes += rhs.bytes;
+        self.chars += rhs.chars;
+        self.max_line_length = cmp::max(self.max_line_length, rhs.max_line_length);
+        self.line_length += rhs.line_length;
         self.blanks += rhs.blanks;
+        self.add_children(&rhs.children);
+        self.avg_line_length = stats::average_line_length(self.line_length, self.total_lines());
         self.stats.push(rhs);
     }
 }
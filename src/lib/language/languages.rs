@@ -3,18 +3,20 @@
 // found in the LICENCE-{APACHE/MIT} file.
 
 use std::borrow::Cow;
+use std::cmp;
 use std::collections::{btree_map, BTreeMap};
 use std::fs::File;
 use std::io::Read;
 use std::iter::IntoIterator;
+use std::mem;
 use std::ops::{AddAssign, Deref, DerefMut};
+use std::path::{Path, PathBuf};
 
 use encoding::{self, DecoderTrap};
 
 // #[cfg(feature = "cbor")]
 // use serde_cbor;
-#[cfg(feature = "json")]
-use serde_json;
+use serde_json::{self, Value};
 #[cfg(feature = "yaml")]
 use serde_yaml;
 #[cfg(feature = "toml-io")]
@@ -22,9 +24,33 @@ use toml;
 use rayon::prelude::*;
 
 use utils::{fs, multi_line};
-use super::{Language, LanguageType};
+use utils::multi_line::Quote;
+use super::{Language, LanguageType, LineKind, MixedPolicy};
 use super::LanguageType::*;
-use stats::Stats;
+use super::language::is_word;
+use duplicates::Duplicates;
+use stats::{self, Marker, Stats};
+
+/// The tag of the SPDX identifier of a file's license.
+const SPDX_TAG: &'static str = "SPDX-License-Identifier:";
+
+/// Text from well known licenses, and the names of the licenses.
+const LICENSE_TEXTS: &'static [(&'static str, &'static str)] = &[
+    ("Licensed under the Apache License, Version 2.0", "Apache-2.0"),
+    ("Permission is hereby granted, free of charge", "MIT"),
+    ("GNU Affero General Public License", "AGPL"),
+    ("GNU Lesser General Public License", "LGPL"),
+    ("GNU General Public License", "GPL"),
+    ("Mozilla Public License", "MPL"),
+    ("Redistribution and use in source and binary forms", "BSD"),
+    ("This is free and unencumbered software released into the public domain", "Unlicense"),
+];
+
+/// The characters which start a pattern in a glob.
+const GLOB_CHARS: &'static [char] = &['*', '?', '['];
+
+/// The names of directories whose files are all tests.
+const TEST_DIRECTORIES: &'static [&'static str] = &["tests", "test", "__tests__"];
 
 #[cfg(not(feature = "json"))]
 const JSON_ERROR: &'static str = "Tokei was not compiled with the `json` flag.";
@@ -33,7 +59,22 @@ const TOML_ERROR: &'static str = "Tokei was not compiled with the `toml-io` flag
 #[cfg(not(feature = "yaml"))]
 const YAML_ERROR: &'static str = "Tokei was not compiled with the `yaml` flag.";
 
-fn count_files(mut language_tuple: (&LanguageType, &mut Language)) {
+thread_local! {
+    /// The definitions of every language, used to count and classify text
+    /// outside of `get_statistics`. They're only generated once per thread.
+    static DEFINITIONS: BTreeMap<LanguageType, Language> = Languages::generate_languages();
+}
+
+/// Calls `f` with the definitions of every language.
+pub fn with_definitions<F, T>(f: F) -> T
+    where F: FnOnce(&BTreeMap<LanguageType, Language>) -> T
+{
+    DEFINITIONS.with(f)
+}
+
+fn count_files(mut language_tuple: (&LanguageType, &mut Language),
+               definitions: &BTreeMap<LanguageType, Language>,
+               roots: &[&str]) {
 
     let (name, ref mut language) = language_tuple;
 
@@ -41,67 +82,1186 @@ fn count_files(mut language_tuple: (&LanguageType, &mut Language)) {
         return;
     }
 
-    let is_fortran = name == &FortranModern || name == &FortranLegacy;
-
     let files: Vec<_> = language.files.drain(..).collect();
     let mut contents = Vec::new();
-    let mut stack = vec![];
-    let mut quote;
 
     for file in files {
         let mut stats = Stats::new(opt_error!(file.to_str(), "Couldn't convert path to String."));
-        stack.clear();
         contents.clear();
-        quote = None;
 
-        rs_error!(rs_error!(File::open(file)).read_to_end(&mut contents));
+        rs_error!(rs_error!(File::open(&file)).read_to_end(&mut contents));
 
+        count_stats(&decode(&contents),
+                    contents.len(),
+                    Some(name),
+                    language,
+                    definitions,
+                    &mut stats);
 
-        let text = match encoding::decode(&contents, DecoderTrap::Replace, encoding::all::UTF_8) {
-            (Ok(string), _) => Cow::Owned(string),
-            (Err(cow), _) => cow,
-        };
+        if is_test_file(relative_path(&file, roots), language) {
+            mark_tests(&mut stats);
+        }
+
+        **language += stats;
+    }
+}
 
-        let lines = text.lines();
+/// The path of the file from the root it was found in, so the directories
+/// above the root aren't taken into account. The root of a glob is the part
+/// of it before the first pattern.
+fn relative_path<'a>(file: &'a Path, roots: &[&str]) -> &'a Path {
+    roots.iter()
+        .filter_map(|root| {
+            let base: PathBuf = Path::new(root)
+                .components()
+                .take_while(|component| {
+                    !component.as_os_str().to_str().is_some_and(|name| name.contains(GLOB_CHARS))
+                })
+                .collect();
+
+            file.strip_prefix(&base).ok()
+        })
+        .min_by_key(|relative| relative.components().count())
+        .unwrap_or(file)
+}
 
-        if language.is_blank() {
-            let count = lines.count();
-            stats.lines += count;
-            stats.code += count;
-            **language += stats;
-            continue;
+/// Whether the file only contains tests, either because it's in a test
+/// directory, or because its name matches one of the language's `test_files`.
+/// The file's path is relative to the root it was found in.
+fn is_test_file(file: &Path, language: &Language) -> bool {
+    let in_test_directory = file.parent().is_some_and(|parent| {
+        parent.components().any(|component| {
+            component.as_os_str().to_str().is_some_and(|name| TEST_DIRECTORIES.contains(&name))
+        })
+    });
+    let name = file.file_name().and_then(|name| name.to_str()).unwrap_or("");
+
+    in_test_directory || language.test_files.iter().any(|pattern| matches_pattern(name, pattern))
+}
+
+/// Whether the name matches the pattern, where `*` matches any text.
+fn matches_pattern(name: &str, pattern: &str) -> bool {
+    match pattern.find('*') {
+        Some(index) => {
+            let (prefix, suffix) = (&pattern[..index], &pattern[index + 1..]);
+            name.len() >= prefix.len() + suffix.len() && name.starts_with(prefix) &&
+            name.ends_with(suffix)
         }
+        None => name == pattern,
+    }
+}
 
+/// Counts all of the code of a file as tests, including the code of the
+/// languages embedded within it.
+fn mark_tests(stats: &mut Stats) {
+    stats.tests = stats.code;
 
-        'line: for line in lines {
-            stats.lines += 1;
-            let no_stack = stack.is_empty();
-            // FORTRAN has a rule where it only counts as a comment if it's the first
-            // character in the column, so removing starting whitespace could cause a
-            // miscount.
-            let line = if is_fortran { line } else { line.trim_left() };
+    for child in stats.children.values_mut() {
+        mark_tests(child);
+    }
+}
+
+/// Decodes the contents of a file, replacing any invalid UTF-8.
+pub fn decode<'a>(contents: &'a [u8]) -> Cow<'a, str> {
+    match encoding::decode(contents, DecoderTrap::Replace, encoding::all::UTF_8) {
+        (Ok(string), _) => Cow::Owned(string),
+        (Err(cow), _) => cow,
+    }
+}
+
+/// Counts the text of a file into `stats`, `bytes` is the size of the file
+/// before it was decoded. The language's type is needed for languages with
+/// special rules, such as Jupyter notebooks.
+pub fn count_stats(text: &str,
+                   bytes: usize,
+                   name: Option<&LanguageType>,
+                   language: &Language,
+                   definitions: &BTreeMap<LanguageType, Language>,
+                   stats: &mut Stats) {
+    stats.bytes = bytes;
+    stats.chars = text.chars().count();
+
+    match name {
+        Some(&Jupyter) => count_notebook(text, definitions, stats),
+        _ => {
+            measure_lines(text.lines(), stats);
+            let header = count_text(text.lines(), 0, language, definitions, stats);
+            stats.license = find_license(text.lines().take(header), language);
+        }
+    }
+
+    stats.avg_line_length = stats::average_line_length(stats.line_length, stats.total_lines());
+}
+
+/// Measures the length of lines of a file, the lines of notebooks are
+/// measured one cell at a time.
+fn measure_lines<'a, I>(lines: I, stats: &mut Stats)
+    where I: Iterator<Item = &'a str>
+{
+    for line in lines {
+        let length = line.chars().count();
+        stats.line_length += length;
+        stats.max_line_length = cmp::max(stats.max_line_length, length);
+    }
+}
+
+/// Finds the license of a file in its header, the comments at the start of
+/// the file. This is either the identifier of an `SPDX-License-Identifier`
+/// tag, or the name of a well known license whose text is in the header.
+fn find_license<'a, I>(header: I, language: &Language) -> Option<String>
+    where I: Iterator<Item = &'a str>
+{
+    let mut text = String::new();
+
+    for line in header {
+        if let Some(index) = line.find(SPDX_TAG) {
+            let mut identifier = line[index + SPDX_TAG.len()..].trim();
+            for &(_, end) in language.multi_line.iter().chain(&language.doc_multi_line) {
+                identifier = identifier.trim_end_matches(end).trim_end();
+            }
+
+            if !identifier.is_empty() {
+                return Some(identifier.to_owned());
+            }
+        }
 
-            if line.trim().is_empty() {
-                stats.blanks += 1;
+        // Leading comment tokens and decorations would split sentences that
+        // continue over several lines.
+        text.push_str(line.trim_start_matches(|c: char| !c.is_alphanumeric()));
+        text.push(' ');
+    }
+
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    LICENSE_TEXTS.iter()
+        .find(|&&(license_text, _)| text.contains(license_text))
+        .map(|&(_, license)| license.to_owned())
+}
+
+/// Counts the lines of a file into `stats`, the lines of languages embedded
+/// in the file are counted into its `children`. The lines are numbered
+/// following the first `offset` lines of the file. Returns the number of
+/// lines in the file's header, the comments and blank lines at its start.
+fn count_text<'a, I>(lines: I,
+                     offset: usize,
+                     language: &Language,
+                     definitions: &BTreeMap<LanguageType, Language>,
+                     stats: &mut Stats)
+                     -> usize
+    where I: Iterator<Item = &'a str>
+{
+    let mut number = 0;
+    let mut header = 0;
+
+    // Lines that can turn out to be documentation are held until a line that
+    // decides it, so each line is only counted once.
+    let mut pending: Vec<(Option<LanguageType>, Line, usize)> = Vec::new();
+
+    classify_text(lines, language, definitions, &mut |child, line| {
+        number += 1;
+
+        let is_header = matches!(line.kind, LineKind::Blank | LineKind::Comment | LineKind::Doc);
+        if is_header && header + 1 == number {
+            header = number;
+        }
+
+        let documented = pending.iter_mut()
+            .rev()
+            .map(|&mut (_, ref mut line, _)| line)
+            .filter(|line| line.kind != LineKind::Blank)
+            .take(line.documents);
+        for documented in documented {
+            *documented = Line {
+                markers: mem::take(&mut documented.markers),
+                ..LineKind::Doc.into()
+            };
+        }
+
+        if line.pending {
+            pending.push((child, line, offset + number));
+            return;
+        }
+
+        for (child, line, number) in pending.drain(..) {
+            add_child_line(child, line, number, language, definitions, stats);
+        }
+        add_child_line(child, line, offset + number, language, definitions, stats);
+    });
+
+    for (child, line, number) in pending {
+        add_child_line(child, line, number, language, definitions, stats);
+    }
+
+    header
+}
+
+/// Classifies each line of `text` the same way as when counting files. Every
+/// line is buffered, as the lines before a declaration can turn out to be
+/// documentation.
+pub fn classify(text: &str,
+                language: &Language,
+                definitions: &BTreeMap<LanguageType, Language>)
+                -> Vec<(usize, LineKind)> {
+    let mut kinds: Vec<LineKind> = Vec::new();
+
+    classify_text(text.lines(), language, definitions, &mut |_, line| {
+        let documented = kinds.iter_mut()
+            .rev()
+            .filter(|kind| **kind != LineKind::Blank)
+            .take(line.documents);
+        for kind in documented {
+            *kind = LineKind::Doc;
+        }
+
+        kinds.push(line.kind);
+    });
+
+    kinds.into_iter().enumerate().map(|(index, kind)| (index + 1, kind)).collect()
+}
+
+/// The lines of code of a file in the file's own language, without their
+/// comments or whitespace, and the line numbers they're on. Lines of
+/// embedded languages are left out.
+pub fn code_lines(text: &str,
+                  language: &Language,
+                  definitions: &BTreeMap<LanguageType, Language>)
+                  -> Vec<(String, usize)> {
+    let mut lines: Vec<(Option<LanguageType>, Line)> = Vec::new();
+
+    classify_text(text.lines(), language, definitions, &mut |child, line| {
+        let documented = lines.iter_mut()
+            .rev()
+            .filter(|&&mut (_, ref previous)| previous.kind != LineKind::Blank)
+            .take(line.documents);
+        for &mut (_, ref mut previous) in documented {
+            previous.kind = LineKind::Doc;
+        }
+
+        lines.push((child, line));
+    });
+
+    text.lines()
+        .zip(lines)
+        .enumerate()
+        .filter_map(|(index, (text, (child, line)))| {
+            if child.is_some() {
+                return None;
+            }
+
+            let code = match line.kind {
+                LineKind::Code | LineKind::Preprocessor => text,
+                LineKind::Mixed => &line.code,
+                _ => return None,
+            };
+            let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+
+            if code.is_empty() { None } else { Some((code, index + 1)) }
+        })
+        .collect()
+}
+
+/// Classifies each line of a file, passing it to `sink` along with the
+/// embedded language it belongs to, if any.
+fn classify_text<'a, I>(lines: I,
+                        language: &Language,
+                        definitions: &BTreeMap<LanguageType, Language>,
+                        sink: &mut FnMut(Option<LanguageType>, Line))
+    where I: Iterator<Item = &'a str>
+{
+    if language.is_blank() {
+        for _ in lines {
+            sink(None, LineKind::Code.into());
+        }
+    } else if !language.code_fences.is_empty() {
+        classify_prose(lines, language, definitions, sink);
+    } else {
+        classify_code(lines, language, definitions, sink);
+    }
+}
+
+/// Counts the cells of a Jupyter notebook. Code cells are counted as the
+/// language of the notebook's kernel, and markdown cells as Markdown. Outputs
+/// and metadata aren't counted.
+fn count_notebook(text: &str,
+                  definitions: &BTreeMap<LanguageType, Language>,
+                  stats: &mut Stats) {
+    let notebook: Value = match serde_json::from_str(text) {
+        Ok(notebook) => notebook,
+        Err(error) => {
+            error!("{}: {}", stats.name, error);
+            return;
+        }
+    };
+
+    let kernel = notebook.find_path(&["metadata", "kernelspec", "language"])
+        .or_else(|| notebook.find_path(&["metadata", "language_info", "name"]))
+        .and_then(Value::as_str)
+        .and_then(LanguageType::from_name)
+        .unwrap_or(Python);
+
+    let cells = match notebook.find("cells").and_then(Value::as_array) {
+        Some(cells) => cells,
+        None => return,
+    };
+
+    // The lines of the cells are numbered as if their sources were one file.
+    let mut offset = 0;
+
+    for cell in cells {
+        let child_type = match cell.find("cell_type").and_then(Value::as_str) {
+            Some("code") => kernel,
+            Some("markdown") => Markdown,
+            _ => continue,
+        };
+
+        // The source is either a single string, or a list of lines.
+        let source = match cell.find("source") {
+            Some(Value::String(source)) => source.clone(),
+            Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+            _ => continue,
+        };
+
+        measure_lines(source.lines(), stats);
+
+        let child = opt_error!(definitions.get(&child_type),
+                               "Unknown Language? Shouldn't happen.");
+        let child_stats = stats.children
+            .entry(child_type)
+            .or_insert_with(|| Stats::new(child_type.name()));
+
+        count_text(source.lines(), offset, child, definitions, child_stats);
+        offset += source.lines().count();
+    }
+}
+
+/// Classifies the lines of a file in a programming language, including any
+/// blocks of other languages embedded within it.
+fn classify_code<'a, I>(lines: I,
+                        language: &Language,
+                        definitions: &BTreeMap<LanguageType, Language>,
+                        sink: &mut FnMut(Option<LanguageType>, Line))
+    where I: Iterator<Item = &'a str>
+{
+    let mut state = LineState::default();
+    let mut child_state = LineState::default();
+    // The language embedded in the current block, the start and end of the
+    // block, and how many blocks with the same start are nested within it.
+    let mut embedded: Option<(LanguageType, &'static str, &'static str)> = None;
+    let mut depth = 0;
+
+    for line in lines {
+        // The line ending the block belongs to the parent language.
+        if let Some((child_type, start, end)) = embedded {
+            if line.trim_start().starts_with(start) && !line.contains(end) {
+                depth += 1;
+            } else if line.contains(end) && depth > 0 {
+                depth -= 1;
+            } else if line.contains(end) {
+                embedded = None;
+            }
+
+            if embedded.is_some() {
+                classify_child(line, child_type, definitions, &mut child_state, sink);
                 continue;
             }
+        }
 
-            for single in &language.line_comment {
-                if line.starts_with(single) {
-                    stats.comments += 1;
-                    continue 'line;
+        let no_stack = state.stack.is_empty();
+        sink(None, classify_line(line, language, &mut state));
+
+        if no_stack {
+            let line = line.trim_start();
+
+            for &(start, end, child_type) in &language.embedded {
+                if line.starts_with(start) && !line.contains(end) {
+                    // The `lang` attribute overrides the default language of
+                    // the block. ie. `<script lang="ts">`
+                    let child_type = lang_attribute(line)
+                        .and_then(LanguageType::from_name)
+                        .unwrap_or(child_type);
+
+                    embedded = Some((child_type, start, end));
+                    depth = 0;
+                    child_state = LineState::default();
+                    break;
                 }
             }
+        }
+    }
+}
 
-            multi_line::handle_multi_line(line, &language, &mut stack, &mut quote);
+/// Gets the value of the `lang` attribute from the opening tag of a block.
+/// The attribute has to follow whitespace, so `xml:lang` isn't mistaken for it.
+fn lang_attribute(tag: &str) -> Option<&str> {
+    let start = match tag.match_indices("lang=")
+        .find(|&(index, _)| tag[..index].ends_with(char::is_whitespace)) {
+        Some((index, _)) => index + "lang=".len(),
+        None => return None,
+    };
+    let value = &tag[start..];
+    let quote = match value.chars().next() {
+        Some(quote) if quote == '"' || quote == '\'' => quote,
+        _ => return None,
+    };
+
+    value[1..].split(quote).next()
+}
 
-            if no_stack {
-                stats.code += 1;
+/// Classifies the lines of a prose file such as Markdown. Text is a comment,
+/// code blocks with a known language are classified as that language, and
+/// any other code blocks are code.
+fn classify_prose<'a, I>(lines: I,
+                         language: &Language,
+                         definitions: &BTreeMap<LanguageType, Language>,
+                         sink: &mut FnMut(Option<LanguageType>, Line))
+    where I: Iterator<Item = &'a str>
+{
+    let mut child_state = LineState::default();
+    // The character and length of the fence of the current code block, and
+    // the language it contains.
+    let mut block: Option<(char, usize, Option<LanguageType>)> = None;
+    let mut previous_blank = true;
+    let mut indented = false;
+
+    for line in lines {
+        let trimmed = line.trim();
+
+        if let Some((fence_char, length, child_type)) = block {
+            // A block is closed by a fence of the same character that's at
+            // least as long as the one that opened it, with no info string.
+            if fence_length(trimmed, fence_char) >= length &&
+               trimmed.trim_matches(fence_char).is_empty() {
+                block = None;
+                sink(None, LineKind::Code.into());
+            } else if let Some(child_type) = child_type {
+                classify_child(line, child_type, definitions, &mut child_state, sink);
+            } else if trimmed.is_empty() {
+                sink(None, LineKind::Blank.into());
             } else {
-                stats.comments += 1;
+                sink(None, LineKind::Code.into());
             }
+            continue;
         }
-        **language += stats;
+
+        if trimmed.is_empty() {
+            sink(None, LineKind::Blank.into());
+            previous_blank = true;
+            continue;
+        }
+
+        let fence = language.code_fences.iter().find(|fence| trimmed.starts_with(*fence));
+
+        if let Some(fence) = fence {
+            // The info string can have attributes after the language.
+            // ie. "```rust,ignore" or "``` {.python}"
+            let fence_char = fence.chars().next().unwrap_or('`');
+            let info = trimmed.trim_start_matches(fence_char)
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .map(|info| info.trim_matches(|c| c == '{' || c == '}' || c == '.'))
+                .find(|info| !info.is_empty());
+
+            block = Some((fence_char,
+                          fence_length(trimmed, fence_char),
+                          info.and_then(LanguageType::from_name)));
+            child_state = LineState::default();
+            indented = false;
+            sink(None, LineKind::Code.into());
+        } else if (previous_blank || indented) &&
+                  (line.starts_with("    ") || line.starts_with('\t')) {
+            indented = true;
+            sink(None, LineKind::Code.into());
+        } else {
+            indented = false;
+            sink(None, LineKind::Comment.into());
+        }
+
+        previous_blank = false;
+    }
+}
+
+/// The number of `fence_char` characters that the line starts with.
+fn fence_length(line: &str, fence_char: char) -> usize {
+    line.chars().take_while(|&c| c == fence_char).count()
+}
+
+/// Classifies a line of a language embedded in another language's file.
+fn classify_child(line: &str,
+                  child_type: LanguageType,
+                  definitions: &BTreeMap<LanguageType, Language>,
+                  state: &mut LineState,
+                  sink: &mut FnMut(Option<LanguageType>, Line)) {
+    if let Some(child) = definitions.get(&child_type) {
+        sink(Some(child_type), classify_line(line, child, state));
+    }
+}
+
+/// A classified line, along with the details that `Stats` needs.
+#[derive(Clone, Debug)]
+struct Line {
+    kind: LineKind,
+    /// Whether a comment is a line comment, rather than part of a block comment.
+    line_comment: bool,
+    /// Whether the line can still turn out to be documentation, depending on
+    /// the lines after it. ie. A line comment in Go.
+    pending: bool,
+    /// The number of lines before the line, other than blank lines, that
+    /// turned out to be documentation. ie. The comments before `func` in Go.
+    documents: usize,
+    /// Whether the line is part of an item marked as a test. ie. A
+    /// `#[cfg(test)]` module in Rust.
+    test: bool,
+    /// The number of decisions in the code of the line.
+    decisions: usize,
+    /// The number of logical lines of code that end on the line.
+    statements: usize,
+    /// The indices of the language's `marker_tags` found in the comments of
+    /// the line.
+    markers: Vec<usize>,
+    /// The code of a line with both code and a comment, without the comment.
+    code: String,
+}
+
+impl From<LineKind> for Line {
+    fn from(kind: LineKind) -> Self {
+        Line {
+            kind,
+            line_comment: false,
+            pending: false,
+            documents: 0,
+            test: false,
+            decisions: 0,
+            statements: 0,
+            markers: Vec::new(),
+            code: String::new(),
+        }
+    }
+}
+
+/// Adds a classified line to `stats`, or to the stats of the embedded
+/// language it belongs to.
+fn add_child_line(child: Option<LanguageType>,
+                  line: Line,
+                  number: usize,
+                  language: &Language,
+                  definitions: &BTreeMap<LanguageType, Language>,
+                  stats: &mut Stats) {
+    match child {
+        Some(child_type) => {
+            let child_stats = stats.children
+                .entry(child_type)
+                .or_insert_with(|| Stats::new(child_type.name()));
+
+            add_line(line, number, &definitions[&child_type], child_stats);
+        }
+        None => add_line(line, number, language, stats),
+    }
+}
+
+/// Adds a classified line to `stats`.
+fn add_line(line: Line, number: usize, language: &Language, stats: &mut Stats) {
+    let code = stats.code;
+    stats.lines += 1;
+
+    match line.kind {
+        LineKind::Blank => stats.blanks += 1,
+        LineKind::Code => stats.code += 1,
+        LineKind::Comment => {
+            stats.comments += 1;
+
+            if line.line_comment {
+                stats.line_comments += 1;
+            } else {
+                stats.block_comments += 1;
+            }
+        }
+        LineKind::Doc => {
+            stats.comments += 1;
+            stats.doc_comments += 1;
+        }
+        LineKind::Preprocessor => {
+            stats.code += 1;
+            stats.preprocessor += 1;
+        }
+        LineKind::Disabled => {
+            stats.comments += 1;
+            stats.disabled += 1;
+        }
+        LineKind::Mixed => {
+            stats.mixed += 1;
+
+            match language.mixed_policy {
+                MixedPolicy::Code => stats.code += 1,
+                MixedPolicy::Comment => stats.comments += 1,
+                MixedPolicy::Both => {
+                    stats.code += 1;
+                    stats.comments += 1;
+                }
+            }
+        }
+    }
+
+    if line.test {
+        stats.tests += stats.code - code;
+    }
+
+    stats.complexity += line.decisions;
+    stats.logical += line.statements;
+
+    for marker in line.markers {
+        stats.markers.push(Marker {
+            tag: language.marker_tags[marker].clone(),
+            line: number,
+        });
+    }
+}
+
+/// The state carried over from one line of a file to the next.
+#[derive(Debug, Default)]
+struct LineState {
+    /// The ends of the multi line comments that are currently open.
+    stack: Vec<&'static str>,
+    /// The quote that is currently open.
+    quote: Option<Quote>,
+    /// Whether the outermost open multi line comment is documentation.
+    doc: bool,
+    /// The number of line comments directly before the current line, these
+    /// are documentation if the line is a declaration. ie. `func` in Go.
+    pending: usize,
+    /// Whether the open quote is a docstring.
+    docstring: bool,
+    /// Whether there has been a statement since the start of the file or the
+    /// header of the last definition, after which a string literal is no
+    /// longer a docstring.
+    statement: bool,
+    /// Whether the header of a definition has started, and hasn't ended yet.
+    /// ie. A function signature spanning several lines in Python.
+    header: bool,
+    /// The number of lines of the string literals directly before the current
+    /// line, which document it if it's a declaration. ie. In Julia.
+    pending_string: usize,
+    /// The identifier that ends the heredoc that is currently open, and
+    /// whether it can be indented. ie. After `<<~` but not `<<` in Ruby.
+    heredoc: Option<(String, bool)>,
+    /// The end of the line anchored block comment that is currently open.
+    line_block: Option<&'static str>,
+    /// Whether the previous line was a preprocessor directive ending in `\`,
+    /// which continues onto this line.
+    directive: bool,
+    /// How many conditional blocks are nested within the disabled block that
+    /// is currently open. ie. `#if 0` in C.
+    disabled: Option<usize>,
+    /// Whether a test attribute has been found, and the block of the item it
+    /// marks hasn't started yet.
+    test_attribute: bool,
+    /// The number of braces open in the block of the test that is currently
+    /// open.
+    test_braces: Option<isize>,
+    /// The number of brackets that are open, which continue a logical line of
+    /// code onto the next line.
+    brackets: isize,
+    /// The number of decision blocks started whose first decision hasn't been
+    /// found yet. ie. A `match` in Rust before its first `=>`.
+    blocks: usize,
+}
+
+/// The byte index of the end of the given column, counting from 1, or the
+/// length of the line if it's shorter than that.
+fn column_index(line: &str, column: usize) -> usize {
+    line.char_indices().nth(column).map_or(line.len(), |(index, _)| index)
+}
+
+/// Whether the line starts with the marker of a line anchored block comment,
+/// followed by a word boundary. ie. `=begin` but not `=beginning` in Ruby.
+fn starts_line_block(line: &str, marker: &str) -> bool {
+    line.starts_with(marker) && line[marker.len()..].chars().next().is_none_or(|c| !is_word(c))
+}
+
+/// Whether the line starts with the given definition, which has to be a
+/// whole word if it ends in one. ie. `struct` but not `structure = 1`.
+fn starts_definition(line: &str, definition: &str) -> bool {
+    line.starts_with(definition) &&
+    (!definition.ends_with(is_word) || !line[definition.len()..].starts_with(is_word))
+}
+
+/// Whether the given column of the line marks it as continuing the previous
+/// line. ie. Any character other than a space or `0` in column 6 of fixed
+/// form FORTRAN, unless a comment starts before it, or `-` in COBOL.
+fn is_continuation(line: &str, column: usize, language: &Language) -> bool {
+    let before = line[..column_index(line, column.saturating_sub(1))].trim_start();
+    if language.line_comment.iter().any(|single| before.starts_with(single)) {
+        return false;
+    }
+
+    match (line.chars().nth(column.saturating_sub(1)), language.continuation_mark) {
+        (Some(c), Some(mark)) => c == mark,
+        (Some(c), None) => !c.is_whitespace() && c != '0',
+        (None, _) => false,
+    }
+}
+
+/// Classifies a single line, `state` carries multi line comments and quotes
+/// over to the next line.
+fn classify_line(line: &str, language: &Language, state: &mut LineState) -> Line {
+    let mut classified = classify_contents(line, language, state);
+
+    // Lines that are only a comment are searched for markers as a whole, as
+    // most of them are classified without looking through the line.
+    if classified.kind == LineKind::Comment || classified.kind == LineKind::Doc {
+        classified.markers.clear();
+        multi_line::find_markers(line, language, &mut classified.markers);
+    }
+
+    classified
+}
+
+/// Classifies the contents of a single line, apart from the markers of lines
+/// that are only a comment.
+fn classify_contents(line: &str, language: &Language, state: &mut LineState) -> Line {
+    if language.is_blank() {
+        return LineKind::Code.into();
+    }
+
+    let line = match language.last_column {
+        Some(column) => &line[..column_index(line, column)],
+        None => line,
+    };
+
+    // Only blank lines can come between a docstring and its declaration.
+    let pending_string = state.pending_string;
+    state.pending_string = 0;
+
+    if line[column_index(line, language.sequence_columns)..].trim().is_empty() {
+        state.pending = 0;
+        state.pending_string = pending_string;
+        return Line {
+            pending: pending_string > 0,
+            test: track_tests("", language, false, 0, state),
+            ..LineKind::Blank.into()
+        };
+    }
+
+    if !language.column_comments.is_empty() {
+        // Columns count from 1, so a column of 0 is taken to be the first.
+        let column = &line[column_index(line, language.comment_column.saturating_sub(1))..];
+
+        if language.column_comments.iter().any(|comment| column.starts_with(comment)) {
+            state.pending += 1;
+            return Line {
+                line_comment: true,
+                pending: true,
+                test: track_tests("", language, false, 0, state),
+                ..LineKind::Comment.into()
+            };
+        }
+    }
+
+    let untrimmed = line;
+    let line = match language.continuation_column {
+        Some(column) if is_continuation(line, column, language) => {
+            let rest = line[column_index(line, column)..].trim_start();
+
+            // A continuation line always continues a statement, which is code.
+            if rest.is_empty() {
+                state.pending = 0;
+                return Line {
+                    test: track_tests("", language, false, 0, state),
+                    ..LineKind::Code.into()
+                };
+            }
+
+            rest
+        }
+        _ => line.trim_start(),
+    };
+
+    if let Some((terminator, indented)) = state.heredoc.take() {
+        let end = if indented { line } else { untrimmed };
+        if !is_heredoc_terminator(end, &terminator) {
+            state.heredoc = Some((terminator, indented));
+        }
+        return Line { test: track_tests("", language, false, 0, state), ..LineKind::Code.into() };
+    }
+
+    // Line anchored block comments have to start in the first column, so
+    // they're checked against the line before it's trimmed.
+    if let Some(end) = state.line_block {
+        if starts_line_block(untrimmed, end) {
+            state.line_block = None;
+        }
+        return Line {
+            test: track_tests("", language, false, 0, state),
+            ..LineKind::Comment.into()
+        };
+    }
+
+    if state.stack.is_empty() && state.quote.is_none() {
+        let block = language.line_blocks
+            .iter()
+            .find(|&&(start, _)| starts_line_block(untrimmed, start));
+
+        if let Some(&(_, end)) = block {
+            state.line_block = Some(end);
+            state.pending = 0;
+            return Line {
+                test: track_tests("", language, false, 0, state),
+                ..LineKind::Comment.into()
+            };
+        }
+    }
+
+    if let Some(depth) = state.disabled {
+        let test = track_tests("", language, false, 0, state);
+        return Line { test, ..classify_disabled(line, language, depth, state) };
+    }
+
+    if state.directive {
+        let contents =
+            multi_line::handle_multi_line(line, language, &mut state.stack, &mut state.quote);
+        state.directive = line.trim_end().ends_with('\\');
+        return Line {
+            test: track_tests("", language, false, contents.braces, state),
+            ..LineKind::Preprocessor.into()
+        };
+    }
+
+    if state.docstring {
+        let contents =
+            multi_line::handle_multi_line(line, language, &mut state.stack, &mut state.quote);
+        state.docstring = state.quote.is_some();
+        return Line {
+            test: track_tests("", language, false, contents.braces, state),
+            ..LineKind::Doc.into()
+        };
+    }
+
+    if pending_string > 0 && state.quote.is_some() {
+        let test = track_tests("", language, false, 0, state);
+        let string = classify_pending_string(line, language, pending_string, state);
+        return Line { test, ..string };
+    }
+
+    for doc in &language.doc_line_comment {
+        if line.starts_with(doc) {
+            state.pending = 0;
+            return Line {
+                test: track_tests("", language, false, 0, state),
+                ..LineKind::Doc.into()
+            };
+        }
+    }
+
+    if language.line_comment.iter().any(|single| line.starts_with(single)) ||
+       language.starts_with_keyword_comment(line) {
+        state.pending += 1;
+        return Line {
+            line_comment: true,
+            pending: true,
+            test: track_tests("", language, false, 0, state),
+            ..LineKind::Comment.into()
+        };
+    }
+
+    // A line starting with a multi line comment is a comment, even if the
+    // comment ends on the same line.
+    let mut starts_comment = false;
+    if state.stack.is_empty() && state.quote.is_none() {
+        if language.doc_multi_line.iter().any(|&(start, _)| line.starts_with(start)) {
+            starts_comment = true;
+            state.doc = true;
+        } else if language.multi_line
+            .iter()
+            .chain(&language.nested_comments)
+            .any(|&(start, _)| line.starts_with(start)) {
+            starts_comment = true;
+            state.doc = false;
+        }
+    }
+
+    let no_stack = state.stack.is_empty();
+    // A line inside of a string is code, but not the start of a statement,
+    // so it can't be a directive or an attribute.
+    let in_quote = state.quote.is_some();
+
+    if no_stack && !in_quote && is_docstring(line, language, state) {
+        let test = track_tests("", language, false, 0, state);
+        return Line { test, ..classify_docstring(line, language, state) };
+    }
+
+    if no_stack && !in_quote && !language.docstring_declarations.is_empty() &&
+       language.quotes.iter().any(|&(start, _, _)| line.starts_with(start)) {
+        let test = track_tests("", language, false, 0, state);
+        return Line { test, ..classify_pending_string(line, language, 0, state) };
+    }
+
+    let starts_code = no_stack && !in_quote && !starts_comment;
+    let header = state.header ||
+                 starts_code &&
+                 language.docstrings.iter().any(|definition| starts_definition(line, definition));
+    // The comments and quotes open before the line, to find its code again
+    // without its comments once it's known to be needed.
+    let (stack, quote) = (state.stack.clone(), state.quote.clone());
+    let code_text = || {
+        multi_line::code_text(line, language, &mut stack.clone(), &mut quote.clone())
+    };
+
+    let contents = multi_line::handle_multi_line(line,
+                                                 language,
+                                                 &mut state.stack,
+                                                 &mut state.quote);
+    let mut documents = 0;
+
+    if starts_code {
+        // Documentation comments don't start in the middle of a line.
+        state.doc = false;
+
+        if language.doc_declarations.iter().any(|declaration| line.starts_with(declaration)) {
+            documents = state.pending;
+        } else if language.docstring_declarations
+            .iter()
+            .any(|declaration| starts_definition(line, declaration)) {
+            documents = pending_string;
+        }
+
+        state.statement = true;
+        state.heredoc = find_heredoc(line, language);
+    }
+
+    state.pending = 0;
+
+    let kind = if starts_code && language.directives.iter().any(|d| line.starts_with(d)) {
+        state.directive = line.trim_end().ends_with('\\');
+
+        if let Some(("if", condition)) = split_directive(line, language) {
+            if language.disable_blocks && language.disabled_conditions.contains(&condition) {
+                state.disabled = Some(0);
+            }
+        }
+
+        LineKind::Preprocessor
+    } else if contents.code && contents.comment {
+        LineKind::Mixed
+    } else if starts_code || in_quote {
+        LineKind::Code
+    } else if state.doc {
+        LineKind::Doc
+    } else {
+        LineKind::Comment
+    };
+
+    let test = track_tests(line, language, starts_code, contents.braces, state);
+
+    // A `"\n"` terminator ends a logical line at the end of a line of code,
+    // unless the line is continued. ie. In Python.
+    state.brackets = cmp::max(0, state.brackets + contents.brackets);
+    let continues = state.quote.is_some() || state.brackets > 0 ||
+                    line.trim_end().ends_with('\\');
+    let ends_statement = contents.code && language.terminators.contains(&"\n") && !continues;
+
+    // A docstring can only be the first statement after a complete header.
+    // The code of a header, without its comments, shows whether it ends in
+    // `:` and so starts a body that can have a docstring.
+    state.header = header && continues;
+    if header && !continues {
+        state.statement = !code_text().trim_end().ends_with(':');
+    }
+    let statements = contents.statements + if ends_statement { 1 } else { 0 };
+
+    // The first decisions on the line can be those of blocks started before it.
+    let first = cmp::min(state.blocks, contents.decisions);
+    state.blocks = state.blocks - first + contents.blocks;
+
+    // Decisions in directives are made when compiling, not running, the code.
+    let decisions = if kind == LineKind::Preprocessor { 0 } else { contents.decisions - first };
+
+    Line {
+        documents,
+        test,
+        decisions,
+        statements,
+        markers: contents.markers,
+        code: if kind == LineKind::Mixed { code_text() } else { String::new() },
+        ..kind.into()
+    }
+}
+
+/// Tracks the blocks of items marked with test attributes by counting their
+/// braces, returning whether the line is part of a test. The item ends at the
+/// end of its block, or at a `;` if it doesn't have one.
+/// ie. `#[cfg(test)] mod tests;` Lines classified before their code is
+/// looked through, such as the lines of a heredoc, are given as `""`, so
+/// only their `braces` are tracked.
+fn track_tests(line: &str,
+               language: &Language,
+               starts_code: bool,
+               braces: isize,
+               state: &mut LineState)
+               -> bool {
+    if let Some(open) = state.test_braces {
+        let open = open + braces;
+        state.test_braces = if open > 0 { Some(open) } else { None };
+        return true;
+    }
+
+    if starts_code && language.test_attributes.iter().any(|attribute| line.starts_with(attribute)) {
+        state.test_attribute = true;
+    }
+
+    let test = state.test_attribute;
+    if test && line.contains('{') {
+        state.test_attribute = false;
+        state.test_braces = if braces > 0 { Some(braces) } else { None };
+    } else if test && line.trim_end().ends_with(';') {
+        state.test_attribute = false;
+    }
+
+    test
+}
+
+/// Classifies a line of a block disabled by the preprocessor, which ends at
+/// the `#else`, `#elif` or `#endif` matching the directive that started it.
+fn classify_disabled(line: &str, language: &Language, depth: usize, state: &mut LineState) -> Line {
+    let (depth, kind) = match split_directive(line, language) {
+        Some(("if", _)) | Some(("ifdef", _)) | Some(("ifndef", _)) => {
+            (Some(depth + 1), LineKind::Disabled)
+        }
+        Some(("else", _)) | Some(("elif", _)) | Some(("endif", _)) if depth == 0 => {
+            (None, LineKind::Preprocessor)
+        }
+        Some(("endif", _)) => (Some(depth - 1), LineKind::Disabled),
+        _ => (Some(depth), LineKind::Disabled),
+    };
+
+    state.disabled = depth;
+    state.pending = 0;
+    kind.into()
+}
+
+/// Splits a preprocessor directive into its name, and its arguments without
+/// any trailing comment. ie. `if` and `0` in `# if 0 // disabled`.
+fn split_directive<'a>(line: &'a str, language: &Language) -> Option<(&'a str, &'a str)> {
+    let prefix = language.directives.iter().find(|&directive| line.starts_with(directive))?;
+
+    let directive = line[prefix.len()..].trim_start();
+    let end = directive.find(|c| !is_word(c)).unwrap_or(directive.len());
+    let arguments = &directive[end..];
+    let comment = language.line_comment
+        .iter()
+        .cloned()
+        .chain(language.multi_line.iter().map(|&(start, _)| start))
+        .filter_map(|comment| arguments.find(comment))
+        .min()
+        .unwrap_or(arguments.len());
+
+    Some((&directive[..end], arguments[..comment].trim()))
+}
+
+/// Finds the identifier of a heredoc started on the line, ignoring operators
+/// that are inside of quotes, and whether the identifier can be indented when
+/// it ends the heredoc. ie. `EOF` in `cat <<-'EOF'`.
+fn find_heredoc(line: &str, language: &Language) -> Option<(String, bool)> {
+    for operator in &language.heredocs {
+        for (index, _) in line.match_indices(operator) {
+            // Part of a longer operator. ie. `<<<` here strings in Bash.
+            if line[..index].ends_with('<') {
+                continue;
+            }
+
+            let mut stack = Vec::new();
+            let mut quote = None;
+            multi_line::handle_multi_line(&line[..index], language, &mut stack, &mut quote);
+            if quote.is_some() || !stack.is_empty() {
+                continue;
+            }
+
+            // A quoted identifier can be separated from the operator by
+            // whitespace. ie. `print << "EOF";` in Perl.
+            let is_quote = |c| c == '\'' || c == '"' || c == '`';
+            let rest = &line[index + operator.len()..];
+            let spaced = rest.trim_start();
+            let rest = if spaced.starts_with(is_quote) { spaced } else { rest };
+            let identifier: String = rest.trim_start_matches(is_quote)
+                .chars()
+                .take_while(|&c| c.is_alphanumeric() || c == '_')
+                .collect();
+
+            // Only a plain `<<` needs the identifier at the start of the line.
+            if identifier.chars().next().is_some_and(|c| !c.is_numeric()) {
+                return Some((identifier, operator.trim() != "<<"));
+            }
+        }
+    }
+
+    None
+}
+
+/// Whether the line ends a heredoc, allowing for punctuation after the
+/// identifier. ie. `EOT;` in PHP.
+fn is_heredoc_terminator(line: &str, terminator: &str) -> bool {
+    if !line.starts_with(terminator) {
+        return false;
+    }
+
+    let rest = line[terminator.len()..].trim();
+    rest.is_empty() || rest.starts_with(';') || rest.starts_with(',') || rest.starts_with(')')
+}
+
+/// Whether the line starts a docstring, a string literal that is either the
+/// first statement of a file or definition, or follows a documentation
+/// attribute.
+fn is_docstring(line: &str, language: &Language, state: &LineState) -> bool {
+    let mut rest = None;
+
+    if !state.statement && !language.docstrings.is_empty() {
+        rest = Some(line);
+    }
+
+    for attribute in &language.doc_attributes {
+        if let Some(after) = line.strip_prefix(attribute) {
+            rest = Some(after.trim_start());
+        }
+    }
+
+    match rest {
+        Some(rest) => language.quotes.iter().any(|&(start, _, _)| rest.starts_with(start)),
+        None => false,
+    }
+}
+
+/// Classifies a line starting a docstring, which is only documentation if
+/// the string literal stands alone. ie. `"""Docs."""` but not `"a".join(b)`.
+fn classify_docstring(line: &str, language: &Language, state: &mut LineState) -> Line {
+    multi_line::handle_multi_line(line, language, &mut state.stack, &mut state.quote);
+    state.statement = true;
+    state.pending = 0;
+
+    let line = line.trim_end();
+    if state.quote.is_some() ||
+       language.quotes.iter().any(|&(_, end, _)| line.ends_with(end)) {
+        state.docstring = state.quote.is_some();
+        LineKind::Doc.into()
+    } else {
+        LineKind::Code.into()
+    }
+}
+
+/// Classifies a line of a string literal standing alone, which documents the
+/// declaration after it. ie. In Julia. `pending` is the number of lines of the
+/// string before the line.
+fn classify_pending_string(line: &str,
+                           language: &Language,
+                           pending: usize,
+                           state: &mut LineState)
+                           -> Line {
+    let contents =
+        multi_line::handle_multi_line(line, language, &mut state.stack, &mut state.quote);
+    state.statement = true;
+    state.pending = 0;
+
+    if state.quote.is_some() ||
+       language.quotes.iter().any(|&(_, end, _)| line.trim_end().ends_with(end)) {
+        state.pending_string = pending + 1;
+        Line { pending: true, statements: contents.statements, ..LineKind::Code.into() }
+    } else {
+        Line { statements: contents.statements, ..LineKind::Code.into() }
     }
 }
 
@@ -241,8 +1401,21 @@ impl Languages {
     pub fn get_statistics<'a, I>(&mut self, paths: I, ignored: I)
         where I: Into<Cow<'a, [&'a str]>>
     {
-        fs::get_all_files(paths.into(), ignored.into(), &mut self.inner);
-        self.inner.par_iter_mut().for_each(count_files);
+        let paths = paths.into();
+        fs::get_all_files(paths.clone(), ignored.into(), &mut self.inner);
+        // Used to count the languages embedded in other languages' files, with
+        // the same options as the languages being counted.
+        let mut definitions = Self::generate_languages();
+        for (language_type, definition) in &mut definitions {
+            if let Some(language) = self.inner.get(language_type) {
+                definition.mixed_policy = language.mixed_policy;
+                definition.disable_blocks = language.disable_blocks;
+                definition.marker_tags = language.marker_tags.clone();
+            }
+        }
+        self.inner.par_iter_mut().for_each(|language_tuple| {
+            count_files(language_tuple, &definitions, &paths)
+        });
     }
 
     /// Constructs a new, blank `Languages`.
@@ -265,19 +1438,61 @@ impl Languages {
     /// let mut languages = Languages::new();
     /// languages.get_statistics(vec!["doesnt/exist"], vec![".git"]);
     ///
-    /// let empty_map = languages.remove_empty();
-    /// let new_map: BTreeMap<LanguageType, Language> = BTreeMap::new();
+    /// let empty_map = languages.remove_empty();
+    /// let new_map: BTreeMap<LanguageType, Language> = BTreeMap::new();
+    ///
+    /// assert_eq!(empty_map, new_map);
+    /// ```
+    pub fn remove_empty(&self) -> BTreeMap<LanguageType, Language> {
+        let mut map = BTreeMap::new();
+
+        for (name, language) in &self.inner {
+            if !language.is_empty() {
+                map.insert(name.clone(), language.clone());
+            }
+        }
+        map
+    }
+
+    /// Finds the blocks of at least `min_lines` lines of code that are
+    /// repeated, in and across the files of each language. Comments and
+    /// whitespace are ignored. The files are read again, so this should be
+    /// called after `get_statistics`.
+    ///
+    /// ```no_run
+    /// # use tokei::*;
+    /// let mut languages = Languages::new();
+    /// languages.get_statistics(&*vec!["."], &*vec![".git", "target"]);
     ///
-    /// assert_eq!(empty_map, new_map);
+    /// for (name, duplicates) in languages.duplicates(6) {
+    ///     println!("{}: {:.2}%", name, duplicates.percentage());
+    /// }
     /// ```
-    pub fn remove_empty(&self) -> BTreeMap<LanguageType, Language> {
+    pub fn duplicates(&self, min_lines: usize) -> BTreeMap<LanguageType, Duplicates> {
+        let definitions = Self::generate_languages();
         let mut map = BTreeMap::new();
 
         for (name, language) in &self.inner {
-            if !language.is_empty() {
-                map.insert(name.clone(), language.clone());
+            // Notebooks aren't code, but contain the code of other languages.
+            if language.stats.is_empty() || *name == Jupyter {
+                continue;
+            }
+
+            let mut files = Vec::new();
+            let mut contents = Vec::new();
+
+            for stats in &language.stats {
+                contents.clear();
+
+                rs_error!(rs_error!(File::open(&stats.name)).read_to_end(&mut contents));
+
+                let lines = code_lines(&decode(&contents), language, &definitions);
+                files.push((stats.name.clone(), lines));
             }
+
+            map.insert(*name, Duplicates::find(&files, min_lines));
         }
+
         map
     }
 
@@ -487,7 +1702,7 @@ mod accuracy_tests {
         let tmp_dir = TempDir::new("test").expect("Couldn't create temp dir");
         let file_name = tmp_dir.path().join(file_name);
         let mut file = File::create(&file_name).expect("Couldn't create file");
-        file.write(contents.as_bytes()).expect("couldn't write to file");
+        file.write_all(contents.as_bytes()).expect("couldn't write to file");
 
         let mut l = Languages::new();
         let l_type = LanguageType::from_extension(&file_name).expect("Can't find language type");
@@ -553,6 +1768,942 @@ mod accuracy_tests {
 }"#)
     }
 
+    #[test]
+    fn html_embedded_languages() {
+        let tmp_dir = TempDir::new("test").expect("Couldn't create temp dir");
+        let file_name = tmp_dir.path().join("embedded.html");
+        let mut file = File::create(&file_name).expect("Couldn't create file");
+        file.write_all(br#"<html>
+    <head>
+        <style type="text/css">
+            body { color: red; }
+            p { margin: 0; }
+        </style>
+        <script src="app.js"></script>
+        <script>
+            // Say hello
+
+            console.log("</style>");
+        </script>
+    </head>
+</html>"#)
+            .expect("couldn't write to file");
+
+        let mut l = Languages::new();
+        l.get_statistics(vec![file_name.to_str().unwrap()], vec![]);
+        let html = l.get(&LanguageType::Html).expect("Couldn't find language");
+        let css = &html.children[&LanguageType::Css];
+        let javascript = &html.children[&LanguageType::JavaScript];
+
+        assert_eq!(9, html.code);
+        assert_eq!((2, 0, 0), (css.code, css.comments, css.blanks));
+        assert_eq!((1, 1, 1), (javascript.code, javascript.comments, javascript.blanks));
+        assert_eq!(html.stats[0].children, html.children);
+    }
+
+    #[test]
+    fn markdown_code_blocks() {
+        let tmp_dir = TempDir::new("test").expect("Couldn't create temp dir");
+        let file_name = tmp_dir.path().join("code_blocks.md");
+        let mut file = File::create(&file_name).expect("Couldn't create file");
+        file.write_all(br#"# Example
+
+Some prose about the example.
+
+```rust,ignore
+// A comment
+fn main() {}
+```
+
+~~~ python
+print("hello")
+~~~
+
+    indented code
+
+```
+plain code
+```"#)
+            .expect("couldn't write to file");
+
+        let mut l = Languages::new();
+        l.get_statistics(vec![file_name.to_str().unwrap()], vec![]);
+        let markdown = l.get(&LanguageType::Markdown).expect("Couldn't find language");
+        let rust = &markdown.children[&LanguageType::Rust];
+        let python = &markdown.children[&LanguageType::Python];
+
+        assert_eq!((8, 2, 5), (markdown.code, markdown.comments, markdown.blanks));
+        assert_eq!((1, 1), (rust.code, rust.comments));
+        assert_eq!(1, python.code);
+    }
+
+    #[test]
+    fn nested_code_fences() {
+        use language::LineKind::*;
+
+        let markdown: Vec<_> = LanguageType::Markdown
+            .classify("````text\n```rust\nlet x = 1;\n```\n```` \nProse.\n~~~\n```\n~~~~\nProse.")
+            .map(|(_, kind)| kind)
+            .collect();
+
+        assert_eq!(vec![Code, Code, Code, Code, Code, Comment, Code, Code, Code, Comment],
+                   markdown);
+    }
+
+    #[test]
+    fn vue_single_file_component() {
+        let tmp_dir = TempDir::new("test").expect("Couldn't create temp dir");
+        let file_name = tmp_dir.path().join("component.vue");
+        let mut file = File::create(&file_name).expect("Couldn't create file");
+        file.write_all(br#"<template>
+  <h1>Items</h1>
+  <ul>
+    <template v-for="item in items">
+      <li>{{ item }}</li>
+    </template>
+  </ul>
+</template>
+
+<script lang="ts">
+// The items
+export default { data: () => ({ items: [] as string[] }) }
+</script>
+
+<style lang='scss'>
+ul { li { color: red; } }
+</style>"#)
+            .expect("couldn't write to file");
+
+        let mut l = Languages::new();
+        l.get_statistics(vec![file_name.to_str().unwrap()], vec![]);
+        let vue = l.get(&LanguageType::Vue).expect("Couldn't find language");
+        let html = &vue.children[&LanguageType::Html];
+        let typescript = &vue.children[&LanguageType::TypeScript];
+        let sass = &vue.children[&LanguageType::Sass];
+
+        assert_eq!((6, 0, 2), (vue.code, vue.comments, vue.blanks));
+        assert_eq!(6, html.code);
+        assert_eq!((1, 1), (typescript.code, typescript.comments));
+        assert_eq!(1, sass.code);
+        assert!(!vue.children.contains_key(&LanguageType::JavaScript));
+    }
+
+    #[test]
+    fn lang_attributes() {
+        assert_eq!(Some("ts"), lang_attribute(r#"<script lang="ts">"#));
+        assert_eq!(Some("ts"), lang_attribute(r#"<script xml:lang="en" lang='ts'>"#));
+        assert_eq!(None, lang_attribute(r#"<template xml:lang="en">"#));
+        assert_eq!(None, lang_attribute(r#"<a hreflang="en">"#));
+    }
+
+    #[test]
+    fn jupyter_notebook_cells() {
+        let tmp_dir = TempDir::new("test").expect("Couldn't create temp dir");
+        let file_name = tmp_dir.path().join("analysis.ipynb");
+        let mut file = File::create(&file_name).expect("Couldn't create file");
+        file.write_all(br##"{
+ "cells": [
+  {
+   "cell_type": "markdown",
+   "metadata": {},
+   "source": ["# Analysis\n", "\n", "Loads the data."]
+  },
+  {
+   "cell_type": "code",
+   "execution_count": 1,
+   "metadata": {},
+   "outputs": [{"name": "stdout", "output_type": "stream", "text": ["42\n"]}],
+   "source": ["# TODO: Explain the answer\n", "x = 42\n", "\n", "print(x)"]
+  },
+  {
+   "cell_type": "raw",
+   "metadata": {},
+   "source": "Not counted."
+  }
+ ],
+ "metadata": {
+  "kernelspec": {"display_name": "Julia 1.0", "language": "julia", "name": "julia-1.0"}
+ },
+ "nbformat": 4,
+ "nbformat_minor": 2
+}"##)
+            .expect("couldn't write to file");
+
+        let mut l = Languages::new();
+        l.get_statistics(vec![file_name.to_str().unwrap()], vec![]);
+        let jupyter = l.get(&LanguageType::Jupyter).expect("Couldn't find language");
+        let julia = &jupyter.children[&LanguageType::Julia];
+        let markdown = &jupyter.children[&LanguageType::Markdown];
+
+        assert!(!jupyter.is_empty());
+        assert_eq!(0, jupyter.lines);
+        assert_eq!((2, 1, 1), (julia.code, julia.comments, julia.blanks));
+        assert_eq!((0, 2, 1), (markdown.code, markdown.comments, markdown.blanks));
+        assert!(!jupyter.children.contains_key(&LanguageType::Python));
+        assert_eq!(vec![Marker { tag: "TODO".to_owned(), line: 4 }], julia.markers);
+    }
+
+    #[test]
+    fn comment_categories() {
+        let tmp_dir = TempDir::new("test").expect("Couldn't create temp dir");
+        let rust = tmp_dir.path().join("lib.rs");
+        let go = tmp_dir.path().join("main.go");
+        File::create(&rust)
+            .and_then(|mut file| {
+                file.write(br#"//! Crate documentation.
+
+/// Adds one.
+fn add_one(x: u32) -> u32 {
+    // Plain comment.
+    x + 1
+}
+
+/**
+ * Subtracts one.
+ */
+fn sub_one(x: u32) -> u32 {
+    /* Block
+       comment. */
+    x - 1
+}
+"#)
+            })
+            .expect("couldn't write to file");
+        File::create(&go)
+            .and_then(|mut file| {
+                file.write(br#"package main
+
+// Answer returns the answer.
+func Answer() int {
+    // Not documentation.
+    return 42
+}
+"#)
+            })
+            .expect("couldn't write to file");
+
+        let mut l = Languages::new();
+        l.get_statistics(vec![rust.to_str().unwrap(), go.to_str().unwrap()], vec![]);
+        let rust = &l[&LanguageType::Rust];
+        let go = &l[&LanguageType::Go];
+
+        assert_eq!((5, 8), (rust.doc_comments, rust.comments));
+        assert_eq!((1, 2), (rust.line_comments, rust.block_comments));
+        assert_eq!((1, 1, 0), (go.doc_comments, go.line_comments, go.block_comments));
+    }
+
+    #[test]
+    fn docstrings() {
+        let tmp_dir = TempDir::new("test").expect("Couldn't create temp dir");
+        let python = tmp_dir.path().join("answer.py");
+        let elixir = tmp_dir.path().join("answer.ex");
+        File::create(&python)
+            .and_then(|mut file| {
+                file.write(br#""""Module documentation."""
+
+import os
+
+
+class Answer:
+    '''
+    Class documentation.
+    '''
+
+    def get(self):
+        """Method documentation."""
+        value = """
+        Not documentation.
+        """
+        return 42
+
+    def join(self):
+        "-".join(["a", "b"])
+"#)
+            })
+            .expect("couldn't write to file");
+        File::create(&elixir)
+            .and_then(|mut file| {
+                file.write(br#"defmodule Answer do
+  @moduledoc """
+  Module documentation.
+  """
+
+  @doc "Function documentation."
+  def get, do: "Not documentation."
+end
+"#)
+            })
+            .expect("couldn't write to file");
+
+        let mut l = Languages::new();
+        l.get_statistics(vec![python.to_str().unwrap(), elixir.to_str().unwrap()], vec![]);
+        let python = &l[&LanguageType::Python];
+        let elixir = &l[&LanguageType::Elixir];
+
+        assert_eq!((9, 5, 5), (python.code, python.comments, python.doc_comments));
+        assert_eq!((3, 4, 4), (elixir.code, elixir.comments, elixir.doc_comments));
+    }
+
+    #[test]
+    fn docstring_positions() {
+        use language::LineKind::*;
+
+        let python: Vec<_> = LanguageType::Python
+            .classify(r#"def area(width,
+         height):
+    """Documentation."""
+    return width * height
+
+def one(): return 1
+"Not documentation."
+
+class Shape:  # A shape.
+    "Documentation."
+"#)
+            .map(|(_, kind)| kind)
+            .collect();
+        let julia: Vec<_> = LanguageType::Julia
+            .classify(r#"x = 1
+"""
+Documentation.
+"""
+function f() end
+
+"Documentation."
+
+struct S end
+"Not documentation."
+y = 2
+# Not documentation.
+"Not documentation."
+"#)
+            .map(|(_, kind)| kind)
+            .collect();
+
+        assert_eq!(vec![Code, Code, Doc, Code, Blank, Code, Code, Blank, Mixed, Doc], python);
+        assert_eq!(vec![Code, Doc, Doc, Doc, Code, Blank, Doc, Blank, Code, Code, Code, Comment,
+                        Code],
+                   julia);
+    }
+
+    #[test]
+    fn rust_raw_strings_and_lifetimes() {
+        test_accuracy("rust_raw_strings_and_lifetimes.rs",
+                      7,
+                      r###"fn main<'a>(path: &'a str) {
+    let dir = r"C:\"; /* comment */
+    let quote = '"';
+    let escaped = '\''; // "
+    let comment = r#"/* "# ;
+    let lifetime: &'static str = "*/";
+}
+"###);
+    }
+
+    #[test]
+    fn cpp_raw_strings() {
+        test_accuracy("cpp_raw_strings.cpp",
+                      6,
+                      r#"int main() {
+    auto query = R"sql(
+        SELECT /* ")" */ 1;
+    )sql"; /* comment */
+    return 0;
+}
+"#);
+    }
+
+    #[test]
+    fn csharp_verbatim_strings() {
+        test_accuracy("csharp_verbatim_strings.cs",
+                      4,
+                      r#"class Program {
+    string path = @"C:\"; /* comment */
+    string quote = @"say ""/*hello*/"" ";
+}
+/* "" */
+"#);
+    }
+
+    #[test]
+    fn sql_doubled_quotes() {
+        test_accuracy("sql_doubled_quotes.sql",
+                      2,
+                      r#"SELECT 'It''s /* not a comment', "a ""quoted"" /* name";
+/* comment */
+SELECT 'C:\';
+-- comment
+"#);
+    }
+
+    #[test]
+    fn pascal_doubled_quotes() {
+        test_accuracy("pascal_doubled_quotes.pas",
+                      4,
+                      r#"begin
+  writeln('It''s { not a comment');
+  writeln('C:\'); { comment }
+end.
+{ comment }
+"#);
+    }
+
+    #[test]
+    fn batch_quotes_without_escapes() {
+        test_accuracy("batch_quotes_without_escapes.bat",
+                      3,
+                      r#"@echo off
+set "dir=C:\"
+echo "%dir%"
+REM comment
+"#);
+    }
+
+    #[test]
+    fn bash_heredocs() {
+        test_accuracy("bash_heredocs.sh",
+                      7,
+                      r##"#!/bin/bash
+cat <<-'EOF' > config
+	# Not a comment
+	key = value
+	EOF
+# comment
+echo "<<EOF"
+echo $(( 1 << 2 ))
+cat <<< "# here string"
+"##);
+    }
+
+    #[test]
+    fn ruby_heredocs() {
+        test_accuracy("ruby_heredocs.rb",
+                      5,
+                      r#"query = <<~SQL
+  # Not a comment
+  SELECT 1
+SQL
+# comment
+puts query
+"#);
+    }
+
+    #[test]
+    fn perl_heredocs() {
+        use language::LineKind::*;
+
+        let perl: Vec<_> = LanguageType::Perl
+            .classify("print << \"EOF\";\n# Not a comment\n  EOF\n# Not a comment\nEOF\n# comment")
+            .map(|(_, kind)| kind)
+            .collect();
+
+        assert_eq!(vec![Code, Code, Code, Code, Code, Comment], perl);
+    }
+
+    #[test]
+    fn php_heredocs() {
+        test_accuracy("php_heredocs.php",
+                      6,
+                      r#"<?php
+$text = <<<"EOT"
+// Not a comment
+# Not a comment
+EOT;
+// comment
+echo $text;
+"#);
+    }
+
+    #[test]
+    fn mixed_lines() {
+        let tmp_dir = TempDir::new("test").expect("Couldn't create temp dir");
+        let file_name = tmp_dir.path().join("mixed.rs");
+        File::create(&file_name)
+            .and_then(|mut file| {
+                file.write(br#"fn main() {
+    let x = 5; // explained
+    /* comment */ let y = x;
+    /* a
+       b */ let z = y;
+    let s = "// not a comment";
+}
+"#)
+            })
+            .expect("couldn't write to file");
+
+        let mut counts = Vec::new();
+        for &policy in &[MixedPolicy::Code, MixedPolicy::Comment, MixedPolicy::Both] {
+            let mut l = Languages::new();
+            l.get_mut(&LanguageType::Rust).unwrap().mixed_policy = policy;
+            l.get_statistics(vec![file_name.to_str().unwrap()], vec![]);
+            let rust = &l[&LanguageType::Rust];
+            counts.push((rust.mixed, rust.code, rust.comments));
+        }
+
+        assert_eq!(vec![(3, 6, 1), (3, 3, 4), (3, 6, 4)], counts);
+    }
+
+    #[test]
+    fn classify_lines() {
+        use language::LineKind::*;
+
+        let go: Vec<_> = LanguageType::Go
+            .classify("// Answer is documented.\nfunc Answer() int {\n\n    return 42 // answer\n}")
+            .map(|(_, kind)| kind)
+            .collect();
+        let html: Vec<_> = LanguageType::Html
+            .classify("<!-- page -->\n<script>\n// script\nlet x = 5;\n</script>")
+            .collect();
+
+        assert_eq!(vec![Doc, Code, Blank, Mixed, Code], go);
+        assert_eq!(vec![(1, Comment), (2, Code), (3, Comment), (4, Code), (5, Code)], html);
+    }
+
+    #[test]
+    fn column_rules() {
+        use language::LineKind::*;
+
+        // Anything after column 72 is ignored.
+        let fortran = ["C comment",
+                       "      PRINT *, 'HI' ! greeting",
+                       "      X = 1                                                             ! 01",
+                       "     !X = 2",
+                       "  ! indented comment",
+                       "      Y = 'IT''S ! NOT'",
+                       "      c = 1"]
+            .join("\n");
+        let fortran: Vec<_> = LanguageType::FortranLegacy
+            .classify(&fortran)
+            .map(|(_, kind)| kind)
+            .collect();
+        let cobol: Vec<_> = LanguageType::Cobol
+            .classify("000100 IDENTIFICATION DIVISION.\n000200* comment\n000300/ page\n\
+                       000400     DISPLAY 'IT''S'. *> shown\n000500\n\
+                       000600     MOVE TOTAL-\n000700-    COUNT TO X.\n000800D    *> Debugging.")
+            .map(|(_, kind)| kind)
+            .collect();
+
+        assert_eq!(vec![Comment, Mixed, Code, Code, Comment, Code, Code], fortran);
+        assert_eq!(vec![Code, Comment, Comment, Mixed, Blank, Code, Code, Mixed], cobol);
+    }
+
+    #[test]
+    fn first_comment_column() {
+        use language::LineKind::*;
+
+        for &column in &[0, 1] {
+            let language = Language::new_single(vec!["!"]).column_comments(column, vec!["C"]);
+            let kinds: Vec<_> = language.classify("C comment
+  X = 1").map(|(_, kind)| kind).collect();
+
+            assert_eq!(vec![Comment, Code], kinds);
+        }
+    }
+
+    #[test]
+    fn first_continuation_column() {
+        use language::LineKind::*;
+
+        let language = Language::new_single(vec!["!"]).continuation_column(0);
+        let kinds: Vec<_> = language.classify("x = 1\n&y").map(|(_, kind)| kind).collect();
+
+        assert_eq!(vec![Code, Code], kinds);
+    }
+
+    #[test]
+    fn keyword_comments() {
+        use language::LineKind::*;
+
+        let batch: Vec<_> = LanguageType::Batch
+            .classify("REM comment\nrem comment\nRem\nREMOVE.exe\necho hi & rem greeting")
+            .map(|(_, kind)| kind)
+            .collect();
+        let autoconf: Vec<_> = LanguageType::Autoconf
+            .classify("dnl comment\ndnlsomething\nDNL\nAC_INIT(x) dnl init")
+            .map(|(_, kind)| kind)
+            .collect();
+
+        assert_eq!(vec![Comment, Comment, Comment, Code, Mixed], batch);
+        assert_eq!(vec![Comment, Code, Code, Mixed], autoconf);
+    }
+
+    #[test]
+    fn line_blocks() {
+        use language::LineKind::*;
+
+        let ruby: Vec<_> = LanguageType::Ruby
+            .classify("=begin\nputs 'hidden'\n=end\n  =begin\nx = 1\n=beginning = 2")
+            .map(|(_, kind)| kind)
+            .collect();
+        let perl: Vec<_> = LanguageType::Perl
+            .classify("my $x = 1;\n\n=head1 NAME\n\nExample\n\n=cut\nprint $x;")
+            .map(|(_, kind)| kind)
+            .collect();
+
+        assert_eq!(vec![Comment, Comment, Comment, Code, Code, Code], ruby);
+        assert_eq!(vec![Code, Blank, Comment, Blank, Comment, Blank, Comment, Code], perl);
+    }
+
+    #[test]
+    fn preprocessor() {
+        use language::LineKind::*;
+
+        let source = r#"#include <stdio.h>
+#define MAX(a, b) \
+    ((a) > (b) ? (a) : (b))
+  # pragma once // comment
+
+int main() { return MAX(1, 2); }"#;
+        let c: Vec<_> = LanguageType::C.classify(source).map(|(_, kind)| kind).collect();
+        let stats = Language::new_c().directives(vec!["#"]).count_str(source);
+
+        assert_eq!(vec![Preprocessor, Preprocessor, Preprocessor, Preprocessor, Blank, Code], c);
+        assert_eq!((5, 4), (stats.code, stats.preprocessor));
+    }
+
+    #[test]
+    fn directives_in_strings() {
+        use language::LineKind::*;
+
+        let source = r#"const char *usage = R"(
+#include is not a directive
+)";
+#define USAGE usage"#;
+        let cpp: Vec<_> = LanguageType::Cpp.classify(source).map(|(_, kind)| kind).collect();
+
+        assert_eq!(vec![Code, Code, Code, Preprocessor], cpp);
+    }
+
+    #[test]
+    fn disabled_blocks() {
+        use language::LineKind::*;
+
+        let source = r#"#if 0
+int old() { return "\""; }
+#ifdef DEBUG
+#endif
+#else
+int new();
+#endif
+# if false /* never */
+int never();
+#elif 1
+int maybe();
+#endif"#;
+        let cpp = Languages::generate_languages()[&LanguageType::Cpp].clone().disable_blocks(true);
+        let c: Vec<_> = cpp.classify(source).map(|(_, kind)| kind).collect();
+        let default: Vec<_> = LanguageType::Cpp.classify(source).map(|(_, kind)| kind).collect();
+
+        assert_eq!(vec![Preprocessor, Code, Preprocessor, Preprocessor, Preprocessor, Code],
+                   &default[..6]);
+        assert_eq!(vec![Preprocessor,
+                        Disabled,
+                        Disabled,
+                        Disabled,
+                        Preprocessor,
+                        Code,
+                        Preprocessor,
+                        Preprocessor,
+                        Disabled,
+                        Preprocessor,
+                        Code,
+                        Preprocessor],
+                   c);
+    }
+
+    #[test]
+    fn disabled_blocks_in_strings() {
+        use language::LineKind::*;
+
+        let source = r#"const char *text = R"(
+#if 0
+)";
+int main();
+#endif"#;
+        let cpp = Languages::generate_languages()[&LanguageType::Cpp].clone().disable_blocks(true);
+        let kinds: Vec<_> = cpp.classify(source).map(|(_, kind)| kind).collect();
+
+        assert_eq!(vec![Code, Code, Code, Code, Preprocessor], kinds);
+    }
+
+    #[test]
+    fn test_modules() {
+        let rust = Language::new_c()
+            .set_quotes(vec![("\"", "\"")])
+            .test_attributes(vec!["#[cfg(test)]"]);
+        let stats = rust.count_str(r#"fn main() {}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn it_works() {
+        assert_eq!("}", "}");
+    }
+}
+
+#[cfg(test)] mod more { }
+fn after() {}"#);
+
+        assert_eq!((10, 8), (stats.code, stats.tests));
+
+        // The lines of a heredoc are part of the test, but its braces aren't.
+        let stats = rust.heredocs(vec!["<<"]).count_str(r#"#[cfg(test)]
+mod tests {
+    const S: &str = <<EOS;
+    }
+EOS
+    fn f() {}
+}
+fn after() {}"#);
+
+        assert_eq!((8, 7), (stats.code, stats.tests));
+    }
+
+    #[test]
+    fn test_files() {
+        let tmp_dir = TempDir::new("test").expect("Couldn't create temp dir");
+        let tests = tmp_dir.path().join("tests");
+        ::std::fs::create_dir(&tests).expect("Couldn't create tests dir");
+
+        for file_name in &[tests.join("integration.rs"), tmp_dir.path().join("main_test.go")] {
+            File::create(file_name)
+                .and_then(|mut file| file.write(b"fn main() {\n}\n"))
+                .expect("couldn't write to file");
+        }
+
+        let main = tmp_dir.path().join("main.go");
+        File::create(&main)
+            .and_then(|mut file| file.write(b"package main\n"))
+            .expect("couldn't write to file");
+
+        let mut l = Languages::new();
+        l.get_statistics(vec![tmp_dir.path().to_str().unwrap()], vec![]);
+
+        assert_eq!((2, 2), (l[&LanguageType::Rust].code, l[&LanguageType::Rust].tests));
+        assert_eq!((3, 2), (l[&LanguageType::Go].code, l[&LanguageType::Go].tests));
+    }
+
+    #[test]
+    fn test_directories_above_root() {
+        let tmp_dir = TempDir::new("test").expect("Couldn't create temp dir");
+        let project = tmp_dir.path().join("tests").join("project");
+        ::std::fs::create_dir_all(&project).expect("Couldn't create project dir");
+
+        File::create(project.join("main.rs"))
+            .and_then(|mut file| file.write(b"fn main() {\n}\n"))
+            .expect("couldn't write to file");
+
+        let mut l = Languages::new();
+        l.get_statistics(vec![project.to_str().unwrap()], vec![]);
+
+        assert_eq!((2, 0), (l[&LanguageType::Rust].code, l[&LanguageType::Rust].tests));
+    }
+
+    #[test]
+    fn complexity() {
+        let stats = Language::new_c()
+            .set_quotes(vec![("\"", "\"")])
+            .decisions(vec!["if", "for", "while", "&&", "||"])
+            .count_str(r#"int main(int argc) {
+    // if this is a comment, it doesn't count
+    /* while
+       for */
+    if (argc > 1 && argc < 4) {
+        printf("if || while");
+    }
+    for (int i = 0; i < argc; i++) {}
+    return notify || verify;
+}"#);
+
+        assert_eq!(4, stats.complexity);
+    }
+
+    #[test]
+    fn complexity_of_matches() {
+        let rust = Languages::generate_languages().remove(&LanguageType::Rust).unwrap();
+        let stats = rust.count_str(r#"fn digits(s: &str) -> Result<u8, Error> {
+    let n = s.parse::<u8>()?;
+    match n {
+        0...9 => Ok(1),
+        10...99 if n % 2 == 0 => Ok(2),
+        _ => Ok(3),
+    }
+}
+fn or_zero(x: Option<u8>) -> u8 { match x { Some(x) => x, None => 0 } }"#);
+
+        // `?`, the two other arms of the first `match` and its `if`, and the
+        // other arm of the second `match`.
+        assert_eq!(5, stats.complexity);
+    }
+
+    #[test]
+    fn complexity_of_directives() {
+        let stats = Language::new_c()
+            .directives(vec!["#"])
+            .decisions(vec!["if", "&&", "||"])
+            .count_str("#if defined(A) && defined(B) || \\\n    defined(C)\n#endif\n\
+                        if (a && b) {}");
+
+        assert_eq!((3, 2), (stats.preprocessor, stats.complexity));
+    }
+
+    #[test]
+    fn logical_lines() {
+        let c = Language::new_c()
+            .set_quotes(vec![("\"", "\"")])
+            .terminators(vec![";", "{", "}"])
+            .count_str(r#"int main(void) {
+    printf("%d; %d",
+           1,
+           2);
+    // return 1;
+    return 0;
+}"#);
+        let python = Language::new_hash()
+            .set_quotes(vec![("\"", "\"")])
+            .terminators(vec!["\n"])
+            .count_str(r#"x = [1,
+     2]
+y = 1 + \
+    2
+# print(x)
+print("(", x, y)"#);
+
+        assert_eq!((6, 4), (c.code, c.logical));
+        assert_eq!((5, 3), (python.code, python.logical));
+    }
+
+    #[test]
+    fn markers() {
+        let tmp_dir = TempDir::new("test").expect("Couldn't create temp dir");
+        let file_name = tmp_dir.path().join("markers.c");
+        File::create(&file_name)
+            .and_then(|mut file| {
+                file.write(br#"// TODO: split this up
+int main() {
+    /* FIXME
+       HACK */
+    puts("TODO"); // XXX
+    return TODOS; // TODO(someone)
+}
+"#)
+            })
+            .expect("couldn't write to file");
+
+        let mut l = Languages::new();
+        l.get_statistics(vec![file_name.to_str().unwrap()], vec![]);
+        let c = &l[&LanguageType::C];
+        let markers: Vec<_> = c.stats[0]
+            .markers
+            .iter()
+            .map(|marker| (&*marker.tag, marker.line))
+            .collect();
+
+        assert_eq!(5, c.markers);
+        assert_eq!(vec![("TODO", 1), ("FIXME", 3), ("HACK", 4), ("XXX", 5), ("TODO", 6)],
+                   markers);
+    }
+
+    #[test]
+    fn markers_of_text() {
+        let stats = LanguageType::Python.count_str("# TODO: Count this.\nx = 1");
+
+        assert_eq!(vec![Marker { tag: "TODO".to_owned(), line: 1 }], stats.markers);
+    }
+
+    #[test]
+    fn licenses() {
+        let spdx = Language::new_c()
+            .count_str("/* SPDX-License-Identifier: MIT OR Apache-2.0 */\nint x;");
+        let mit = Language::new_hash().count_str(r#"#!/bin/sh
+# Copyright (c) 2017 Someone
+#
+# Permission is hereby granted, free
+# of charge, to any person obtaining a copy
+
+echo hi"#);
+        let after_code = Language::new_c().count_str("int x;\n// SPDX-License-Identifier: MIT");
+
+        assert_eq!(Some("MIT OR Apache-2.0"), spdx.license.as_deref());
+        assert_eq!(Some("MIT"), mit.license.as_deref());
+        assert_eq!(None, after_code.license);
+    }
+
+    #[test]
+    fn line_lengths() {
+        let c = Language::new_c();
+        let first = c.count_str("int x;\n// é\n\nint longer = 10;\n");
+        let second = c.count_str("int y;\nint z;");
+
+        assert_eq!((31, 30), (first.bytes, first.chars));
+        assert_eq!((16, 6), (first.max_line_length, first.avg_line_length));
+
+        let mut language = Language::new_c();
+        language += first;
+        language += second;
+
+        assert_eq!((44, 43), (language.bytes, language.chars));
+        assert_eq!((16, 6), (language.max_line_length, language.avg_line_length));
+    }
+
+    #[test]
+    fn invalid_utf8_size() {
+        let tmp_dir = TempDir::new("test").expect("Couldn't create temp dir");
+        let file_name = tmp_dir.path().join("invalid.c");
+        File::create(&file_name)
+            .and_then(|mut file| file.write(b"x=\xff\xfe;\n\n"))
+            .expect("couldn't write to file");
+
+        let mut l = Languages::new();
+        l.get_statistics(vec![file_name.to_str().unwrap()], vec![]);
+
+        assert_eq!(7, l[&LanguageType::C].bytes);
+    }
+
+    #[test]
+    fn documented_declarations() {
+        let go = Language::new_c().doc_declarations(vec!["func "]);
+        let documented = go.count_str("// Main does things.\nfunc main() {} // trailing");
+        let inline = go.count_str("/* doc */ func main() {}\nfunc other() {}");
+
+        assert_eq!((1, 0, 1), (documented.doc_comments, documented.line_comments, documented.code));
+        assert_eq!((0, 0, 2), (inline.doc_comments, inline.line_comments, inline.code));
+    }
+
+    #[test]
+    fn python_raw_strings() {
+        use language::LineKind::*;
+
+        let python: Vec<_> = LanguageType::Python
+            .classify(r#"quote = r"\""
+x = 1  # one
+pattern = Rb'\d+\''
+regex = fR'''
+\'''
+'''
+# comment"#)
+            .map(|(_, kind)| kind)
+            .collect();
+
+        assert_eq!(vec![Code, Mixed, Code, Code, Code, Code, Comment], python);
+    }
+
+    #[test]
+    fn embedded_line_lengths() {
+        let html = LanguageType::Html.count_str("<p>Hi</p>\n<script>\nvar x = 1;\n</script>");
+        let notebook = LanguageType::Jupyter.count_str(r#"{"cells": [
+    {"cell_type": "code", "source": ["x = 1\n", "y"]}
+]}"#);
+
+        let mut language = Language::new_c();
+        language += html.clone();
+
+        assert_eq!((10, 9), (html.max_line_length, html.avg_line_length));
+        assert_eq!(html.avg_line_length, html.summarise().avg_line_length);
+        assert_eq!(html.avg_line_length, language.avg_line_length);
+        assert_eq!((5, 3), (notebook.max_line_length, notebook.avg_line_length));
+    }
+
     #[test]
     fn nesting_with_nesting_comments() {
         test_accuracy("nesting_with_nesting_comments.d",
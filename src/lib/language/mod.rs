@@ -7,5 +7,5 @@ pub mod languages;
 pub mod language_type;
 
 pub use self::languages::Languages;
-pub use self::language::Language;
+pub use self::language::{Escape, Language, LineKind, MixedPolicy, DEFAULT_MARKER_TAGS};
 pub use self::language_type::*;
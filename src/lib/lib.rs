@@ -61,7 +61,6 @@ extern crate rayon;
 extern crate serde;
 // #[cfg(feature = "cbor")]
 // extern crate serde_cbor;
-#[cfg(feature = "json")]
 extern crate serde_json;
 #[cfg(feature = "yaml")]
 extern crate serde_yaml;
@@ -5,10 +5,14 @@ use std::borrow::Cow;
 pub enum Sort {
     /// Sort by number blank lines.
     Blanks,
+    /// Sort by number of bytes.
+    Bytes,
     /// Sort by number comments lines.
     Comments,
     /// Sort by number code lines.
     Code,
+    /// Sort by complexity.
+    Complexity,
     /// Sort by number files lines.
     Files,
     /// Sort by number of lines.
@@ -1,4 +1,20 @@
+use std::cmp;
+use std::collections::BTreeMap;
 use std::fmt;
+use std::ops::AddAssign;
+
+use language::LanguageType;
+
+/// A marker tag in a comment, such as `TODO`.
+#[cfg_attr(feature = "io", derive(Deserialize, Serialize))]
+#[derive(Clone, Default, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Marker {
+    /// The tag of the marker.
+    pub tag: String,
+    /// The line of the file the marker is on, counting from 1. The lines of a
+    /// notebook are counted through the sources of its cells, in order.
+    pub line: usize,
+}
 
 /// A struct representing the statistics of a file.
 #[cfg_attr(feature = "io", derive(Deserialize, Serialize))]
@@ -10,6 +26,66 @@ pub struct Stats {
     pub code: usize,
     /// Number of comments within the file. (_includes both multi line, and single line comments_)
     pub comments: usize,
+    /// Number of documentation comments within the file. ie. `///` in Rust.
+    #[cfg_attr(feature = "io", serde(default))]
+    pub doc_comments: usize,
+    /// Number of single line comments within the file, excluding documentation.
+    #[cfg_attr(feature = "io", serde(default))]
+    pub line_comments: usize,
+    /// Number of lines in multi line comments within the file, excluding documentation.
+    #[cfg_attr(feature = "io", serde(default))]
+    pub block_comments: usize,
+    /// Number of lines with both code and a comment within the file. These are counted in `code`
+    /// and `comments` according to the language's `mixed_policy`.
+    #[cfg_attr(feature = "io", serde(default))]
+    pub mixed: usize,
+    /// Number of lines of preprocessor directives within the file. These are also counted in
+    /// `code`.
+    #[cfg_attr(feature = "io", serde(default))]
+    pub preprocessor: usize,
+    /// Number of lines in blocks disabled by the preprocessor within the file. These are also
+    /// counted in `comments`.
+    #[cfg_attr(feature = "io", serde(default))]
+    pub disabled: usize,
+    /// Number of lines of code in tests within the file. These are also counted in `code`.
+    #[cfg_attr(feature = "io", serde(default))]
+    pub tests: usize,
+    /// The number of decisions in the code within the file, an estimate of its cyclomatic
+    /// complexity.
+    #[cfg_attr(feature = "io", serde(default))]
+    pub complexity: usize,
+    /// Number of logical lines of code within the file, counted by the statement terminators and
+    /// block openers in the code.
+    #[cfg_attr(feature = "io", serde(default))]
+    pub logical: usize,
+    /// The marker tags in comments within the file, such as `TODO`.
+    #[cfg_attr(feature = "io", serde(default))]
+    pub markers: Vec<Marker>,
+    /// Number of bytes within the file.
+    #[cfg_attr(feature = "io", serde(default))]
+    pub bytes: usize,
+    /// Number of characters within the file.
+    #[cfg_attr(feature = "io", serde(default))]
+    pub chars: usize,
+    /// The length of the longest line within the file, in characters.
+    #[cfg_attr(feature = "io", serde(default))]
+    pub max_line_length: usize,
+    /// The total length of the lines within the file, in characters, without line endings.
+    /// Languages embedded within the file are included.
+    #[cfg_attr(feature = "io", serde(default))]
+    pub line_length: usize,
+    /// The average length of the lines within the file, in characters, rounded down. Languages
+    /// embedded within the file are included.
+    #[cfg_attr(feature = "io", serde(default))]
+    pub avg_line_length: usize,
+    /// The license of the file found in the comments at its start, either the identifier of an
+    /// `SPDX-License-Identifier` tag, or the name of a well known license. ie. `MIT`.
+    #[cfg_attr(feature = "io", serde(default))]
+    pub license: Option<String>,
+    /// Statistics of the languages embedded within the file. ie. JavaScript in a `<script>`
+    /// block. These lines aren't included in the file's own counts.
+    #[cfg_attr(feature = "io", serde(default))]
+    pub children: BTreeMap<LanguageType, Stats>,
     /// Total number of lines within the file.
     pub lines: usize,
     /// File name.
@@ -27,6 +103,87 @@ impl Stats {
     pub fn new<S: Into<String>>(name: S) -> Self {
         Stats { name: name.into(), ..Self::default() }
     }
+
+    /// Returns the totals of the file, including the languages embedded
+    /// within it.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let mut html = Stats::new("index.html");
+    /// let mut javascript = Stats::new("index.html");
+    /// html.lines += 2;
+    /// javascript.lines += 3;
+    /// html.children.insert(LanguageType::JavaScript, javascript);
+    ///
+    /// assert_eq!(5, html.summarise().lines);
+    /// ```
+    pub fn summarise(&self) -> Self {
+        let mut summary = Stats { children: BTreeMap::new(), ..self.clone() };
+
+        for child in self.children.values() {
+            summary += &child.summarise();
+        }
+        summary
+    }
+
+    /// Returns the number of lines of the file, including the languages
+    /// embedded within it.
+    ///
+    /// ```
+    /// # use tokei::*;
+    /// let mut html = Stats::new("index.html");
+    /// let mut javascript = Stats::new("index.html");
+    /// html.lines += 2;
+    /// javascript.lines += 3;
+    /// html.children.insert(LanguageType::JavaScript, javascript);
+    ///
+    /// assert_eq!(5, html.total_lines());
+    /// ```
+    pub fn total_lines(&self) -> usize {
+        self.children.values().fold(self.lines, |lines, child| lines + child.total_lines())
+    }
+}
+
+impl<'a> AddAssign<&'a Stats> for Stats {
+    fn add_assign(&mut self, rhs: &'a Self) {
+        self.blanks += rhs.blanks;
+        self.code += rhs.code;
+        self.comments += rhs.comments;
+        self.doc_comments += rhs.doc_comments;
+        self.line_comments += rhs.line_comments;
+        self.block_comments += rhs.block_comments;
+        self.mixed += rhs.mixed;
+        self.preprocessor += rhs.preprocessor;
+        self.disabled += rhs.disabled;
+        self.tests += rhs.tests;
+        self.complexity += rhs.complexity;
+        self.logical += rhs.logical;
+        self.markers.extend_from_slice(&rhs.markers);
+        self.bytes += rhs.bytes;
+        self.chars += rhs.chars;
+        self.max_line_length = cmp::max(self.max_line_length, rhs.max_line_length);
+        self.line_length += rhs.line_length;
+        self.lines += rhs.lines;
+
+        for (language_type, child) in &rhs.children {
+            *self.children.entry(*language_type).or_insert_with(|| Stats::new(&*child.name)) +=
+                child;
+        }
+
+        self.avg_line_length = average_line_length(self.line_length, self.total_lines());
+    }
+}
+
+impl AddAssign for Stats {
+    fn add_assign(&mut self, rhs: Self) {
+        *self += &rhs;
+    }
+}
+
+/// The average length of `lines` lines, which are `length` characters long
+/// in total.
+pub fn average_line_length(length: usize, lines: usize) -> usize {
+    length.checked_div(lines).unwrap_or(0)
 }
 
 fn find_char_boundary(s: &str, index: usize) -> usize {
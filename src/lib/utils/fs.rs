@@ -82,25 +82,33 @@ pub fn get_all_files<'a>(paths: Cow<'a, [&'a str]>,
     }
 }
 
-pub fn get_extension<P: AsRef<Path>>(path: P) -> Option<String> {
+/// Returns every extension of the file name, from the longest to the shortest,
+/// ie. `foo.blade.php` gives `blade.php` and `php`. This is so that languages
+/// can be defined by compound extensions, with the longest match winning.
+pub fn get_extensions<P: AsRef<Path>>(path: P) -> Vec<String> {
     let path = path.as_ref();
-    match path.extension() {
-        Some(extension_os) => {
-            match extension_os.to_str() {
-                Some(extension) => Some(extension.to_lowercase()),
-                None => None,
-            }
-        }
-        None => {
-            match get_filetype_from_shebang(path) {
-                // Using String::from here because all file extensions from
-                // get_filetype_from_shebang are guaranteed to be lowercase.
-                Some(extension) => Some(String::from(extension)),
-                None => None,
-            }
+    let file_name = match path.file_name().and_then(|name| name.to_str()) {
+        Some(file_name) => file_name.to_lowercase(),
+        None => return Vec::new(),
+    };
+
+    // A leading dot marks a hidden file rather than an extension, ie. `.bashrc`.
+    let parts: Vec<&str> = file_name.trim_start_matches('.').split('.').collect();
+
+    // A trailing dot is no extension at all, ie. `script.`.
+    if parts.len() > 1 && !file_name.ends_with('.') {
+        (1..parts.len())
+            .map(|index| parts[index..].join("."))
+            .filter(|extension| !extension.is_empty())
+            .collect()
+    } else {
+        match get_filetype_from_shebang(path) {
+            // Using String::from here because all file extensions from
+            // get_filetype_from_shebang are guaranteed to be lowercase.
+            Some(extension) => vec![String::from(extension)],
+            None => Vec::new(),
         }
     }
-
 }
 
 
@@ -108,7 +116,8 @@ pub fn get_extension<P: AsRef<Path>>(path: P) -> Option<String> {
 mod test {
     extern crate tempdir;
     use super::*;
-    use std::fs::create_dir;
+    use std::fs::{create_dir, File};
+    use std::io::Write;
     use language::languages::Languages;
     use language::LanguageType;
     use self::tempdir::TempDir;
@@ -125,4 +134,35 @@ mod test {
 
         assert_eq!(0, l.get(&LanguageType::Rust).unwrap().files.len());
     }
+
+    #[test]
+    fn compound_extensions() {
+        assert_eq!(get_extensions("views/index.blade.php"), vec!["blade.php", "php"]);
+        assert_eq!(get_extensions("archive.TAR.GZ"), vec!["tar.gz", "gz"]);
+        assert_eq!(get_extensions(".hidden.rs"), vec!["rs"]);
+    }
+
+    #[test]
+    fn trailing_dot() {
+        let tmp_dir = TempDir::new("test").expect("Couldn't create temp dir");
+        let file_name = tmp_dir.path().join("script.");
+        File::create(&file_name)
+            .and_then(|mut file| file.write(b"#!/bin/sh\necho hi\n"))
+            .expect("couldn't write to file");
+
+        assert_eq!(get_extensions(&file_name), vec!["sh"]);
+        assert_eq!(LanguageType::from_extension(&file_name), Some(LanguageType::Bash));
+    }
+
+    #[test]
+    fn longest_extension_wins() {
+        assert_eq!(LanguageType::from_extension("views/index.blade.php"),
+                   Some(LanguageType::Blade));
+        assert_eq!(LanguageType::from_extension("index.php"), Some(LanguageType::Php));
+        assert_eq!(LanguageType::from_extension("types.d.ts"),
+                   Some(LanguageType::TypeScriptDeclaration));
+        assert_eq!(LanguageType::from_extension("types.ts"), Some(LanguageType::TypeScript));
+        assert_eq!(LanguageType::from_extension("views/index.html.erb"),
+                   Some(LanguageType::RubyHtml));
+    }
 }
@@ -1,15 +1,97 @@
-use language::Language;
+use std::borrow::Cow;
+use std::str::Chars;
+
+use language::{Escape, Language};
+use language::language::is_word;
+
+/// A quote that is currently open.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Quote {
+    /// The end of the quote, this isn't static for quotes such as C++ raw
+    /// strings, which end with a delimiter chosen by the string.
+    pub end: Cow<'static, str>,
+    pub escape: Escape,
+}
+
+impl Quote {
+    fn new<S: Into<Cow<'static, str>>>(end: S, escape: Escape) -> Self {
+        Quote {
+            end: end.into(),
+            escape,
+        }
+    }
+}
+
+/// What was found on a line, quotes count as code.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Contents {
+    pub code: bool,
+    pub comment: bool,
+    /// The number of `{` minus the number of `}` in the code of the line.
+    pub braces: isize,
+    /// The number of the language's `decisions` in the code of the line,
+    /// other than the first decisions of blocks started on the line.
+    pub decisions: usize,
+    /// The number of the language's `decision_blocks` started on the line
+    /// whose first decision isn't on the line.
+    pub blocks: usize,
+    /// The number of opening brackets minus the number of closing brackets
+    /// in the code of the line, including braces.
+    pub brackets: isize,
+    /// The number of the language's `terminators` in the code of the line.
+    pub statements: usize,
+    /// The indices of the language's `marker_tags` found in the comments of
+    /// the line.
+    pub markers: Vec<usize>,
+}
 
 /// This is used to catch lines like "let x = 5; /* Comment */"
 pub fn handle_multi_line(line: &str,
                          language: &Language,
                          stack: &mut Vec<&'static str>,
-                         quote: &mut Option<&'static str>) {
+                         quote: &mut Option<Quote>)
+                         -> Contents {
+    lex(line, language, stack, quote, None)
+}
+
+/// Returns the text of `line` without its comments, finding them the same
+/// way as `handle_multi_line`. ie. `let x = "//"; // x` gives `let x = "//"; `.
+pub fn code_text(line: &str,
+                 language: &Language,
+                 stack: &mut Vec<&'static str>,
+                 quote: &mut Option<Quote>)
+                 -> String {
+    let mut code = String::new();
+    lex(line, language, stack, quote, Some(&mut code));
+    code
+}
+
+/// Finds the contents of `line`, adding the text that isn't in a comment to
+/// `code` if it's given.
+fn lex(line: &str,
+       language: &Language,
+       stack: &mut Vec<&'static str>,
+       quote: &mut Option<Quote>,
+       mut code: Option<&mut String>)
+       -> Contents {
     let mut chars = line.chars();
     let nested_is_empty = language.nested_comments.is_empty();
+    let mut contents = Contents::default();
+    // The start of the text looked at last, and whether it was code.
+    let mut start = 0;
+    let mut is_code = false;
 
     'window: loop {
         let window = chars.as_str();
+        let offset = line.len() - window.len();
+        if let Some(ref mut code) = code {
+            if is_code {
+                code.push_str(&line[start..offset]);
+            }
+        }
+        start = offset;
+        is_code = false;
+
         if window.is_empty() {
             break;
         }
@@ -17,30 +99,52 @@ pub fn handle_multi_line(line: &str,
 
         let mut end = false;
 
-        if let &mut Some(quote_str) = quote {
-            if window.starts_with("\\") {
-                chars.next();
-                continue;
-            } else if window.starts_with(quote_str) {
-                end = true;
-            }
-        }
+        if let Some(ref open) = *quote {
+            contents.code = true;
+            is_code = true;
 
-        if end {
-            if let &mut Some(quote_str) = quote {
-                *quote = None;
+            let quote_end = &*open.end;
 
-                if quote_str.chars().count() == 1 {
+            match open.escape {
+                Escape::Backslash if window.starts_with("\\") => {
                     chars.next();
+                    continue;
+                }
+                Escape::Custom(escape) if window.starts_with(escape) => {
+                    skip(&mut chars, escape.chars().count());
+                    continue;
                 }
+                Escape::Doubled if window.starts_with(quote_end) &&
+                                   window[quote_end.len()..].starts_with(quote_end) => {
+                    skip(&mut chars, quote_end.chars().count() * 2 - 1);
+                    continue;
+                }
+                _ => {}
+            }
+
+            if !window.starts_with(quote_end) {
                 continue;
             }
+
+            skip(&mut chars, quote_end.chars().count() - 1);
+            end = true;
         }
 
-        if quote.is_some() {
+        if end {
+            *quote = None;
             continue;
         }
 
+        if stack.is_empty() {
+            if let Some((open, length)) = open_quote(window, language) {
+                contents.code = true;
+                is_code = true;
+                *quote = open;
+                skip(&mut chars, length - 1);
+                continue;
+            }
+        }
+
         let mut pop = false;
         if let Some(last) = stack.last() {
             if window.starts_with(last) {
@@ -49,57 +153,223 @@ pub fn handle_multi_line(line: &str,
         }
 
         if pop {
-            stack.pop();
-            chars.next();
-            continue;
-        }
-
-
-        if stack.is_empty() {
-            for &(start, end) in &language.quotes {
-                if window.starts_with(start) {
-                    *quote = Some(end);
-                    chars.next();
-                    continue 'window;
-                }
+            contents.comment = true;
+            if let Some(end) = stack.pop() {
+                skip(&mut chars, end.chars().count() - 1);
             }
+            continue;
         }
 
 
         if stack.is_empty() {
             for comment in &language.line_comment {
                 if window.starts_with(comment) {
+                    contents.comment = true;
+                    find_markers(window, language, &mut contents.markers);
                     break 'window;
                 }
             }
+
+            let previous = line[..line.len() - window.len()].chars().next_back();
+            if !previous.is_some_and(is_word) && language.starts_with_keyword_comment(window) {
+                contents.comment = true;
+                find_markers(window, language, &mut contents.markers);
+                break;
+            }
         }
 
         for &(start, end) in &language.nested_comments {
             if window.starts_with(start) {
+                contents.comment = true;
                 stack.push(end);
-                chars.next();
+                skip(&mut chars, start.chars().count() - 1);
                 continue 'window;
             }
         }
 
-        for &(start, end) in &language.multi_line {
+        for &(start, end) in language.doc_multi_line.iter().chain(&language.multi_line) {
             if window.starts_with(start) {
+                contents.comment = true;
                 if language.nested && nested_is_empty {
                     stack.push(end);
                 } else if stack.len() == 0 {
                     stack.push(end);
                 }
-                chars.next();
+                skip(&mut chars, start.chars().count() - 1);
                 continue 'window;
             }
         }
+
+        if !stack.is_empty() {
+            contents.comment = true;
+
+            if let Some(marker) = starts_marker(line, window, language) {
+                contents.markers.push(marker);
+            }
+        } else if window.starts_with(char::is_whitespace) {
+            is_code = true;
+        } else {
+            contents.code = true;
+            is_code = true;
+
+            if window.starts_with('{') {
+                contents.braces += 1;
+            } else if window.starts_with('}') {
+                contents.braces -= 1;
+            }
+
+            if window.starts_with(['(', '[', '{']) {
+                contents.brackets += 1;
+            } else if window.starts_with([')', ']', '}']) {
+                contents.brackets -= 1;
+            }
+
+            if let Some(decision) = starts_decision(line, window, language) {
+                if contents.blocks > 0 {
+                    contents.blocks -= 1;
+                } else {
+                    contents.decisions += 1;
+                }
+                skip(&mut chars, decision.chars().count() - 1);
+            } else if let Some(block) = language.decision_blocks
+                .iter()
+                .find(|&block| starts_word(line, window, block)) {
+                contents.blocks += 1;
+                skip(&mut chars, block.chars().count() - 1);
+            } else if let Some(terminator) = language.terminators
+                .iter()
+                .find(|&terminator| window.starts_with(terminator)) {
+                contents.statements += 1;
+                skip(&mut chars, terminator.chars().count() - 1);
+            }
+        }
+    }
+
+    contents
+}
+
+/// Finds the literal starting at the beginning of `window`, returning the
+/// quote if the literal is left open, and the number of characters that were
+/// matched. Char literals are closed straight away, and are only literals if
+/// they're closed. ie. `'a'` but not the lifetime `'a`.
+fn open_quote(window: &str, language: &Language) -> Option<(Option<Quote>, usize)> {
+    for &(prefix, open, close, suffix) in &language.delimited_quotes {
+        if !window.starts_with(prefix) {
+            continue;
+        }
+
+        let rest = &window[prefix.len()..];
+        if let Some(index) = rest.find(open) {
+            let delimiter = &rest[..index];
+            let is_delimiter = delimiter.len() <= 16 &&
+                               !delimiter.contains(|c: char| {
+                c.is_whitespace() || c == '\\' || c == '(' || c == ')'
+            });
+
+            if is_delimiter {
+                let end = format!("{}{}{}", close, delimiter, suffix);
+                let length = prefix.chars().count() + delimiter.chars().count() +
+                             open.chars().count();
+                return Some((Some(Quote::new(end, Escape::None)), length));
+            }
+        }
+    }
+
+    for &(start, end) in &language.char_quotes {
+        if !window.starts_with(start) {
+            continue;
+        }
+
+        let mut rest = window[start.len()..].chars();
+        match rest.next() {
+            Some('\\') => {
+                return Some((Some(Quote::new(end, Escape::Backslash)), start.chars().count()))
+            }
+            Some(_) if rest.as_str().starts_with(end) => {
+                let length = start.chars().count() + 1 + end.chars().count();
+                return Some((None, length));
+            }
+            _ => return None,
+        }
+    }
+
+    for &(start, end, escape) in &language.quotes {
+        if window.starts_with(start) {
+            return Some((Some(Quote::new(end, escape)), start.chars().count()));
+        }
+    }
+
+    None
+}
+
+/// Finds the decision at the beginning of `window`, decisions that are
+/// keywords have to be whole words. ie. `if` but not `iffy`.
+fn starts_decision(line: &str, window: &str, language: &Language) -> Option<&'static str> {
+    language.decisions.iter().cloned().find(|&decision| {
+        if !decision.starts_with(is_word) {
+            window.starts_with(decision)
+        } else {
+            starts_word(line, window, decision)
+        }
+    })
+}
+
+/// Finds the marker tag at the beginning of `window`, returning its index in
+/// the language's `marker_tags`. Tags have to be whole words. ie. `TODO` but
+/// not `TODOS`.
+fn starts_marker(line: &str, window: &str, language: &Language) -> Option<usize> {
+    language.marker_tags.iter().position(|tag| starts_word(line, window, tag))
+}
+
+/// Whether `window`, the rest of `line`, starts with `word` as a whole word.
+fn starts_word(line: &str, window: &str, word: &str) -> bool {
+    if !window.starts_with(word) {
+        return false;
+    }
+
+    let previous = line[..line.len() - window.len()].chars().next_back();
+    let next = window[word.len()..].chars().next();
+    !previous.is_some_and(is_word) && !next.is_some_and(is_word)
+}
+
+/// Finds the marker tags in a comment, adding the index of each of them in
+/// the language's `marker_tags` to `markers`.
+pub fn find_markers(comment: &str, language: &Language, markers: &mut Vec<usize>) {
+    if language.marker_tags.is_empty() {
+        return;
+    }
+
+    for (index, _) in comment.char_indices() {
+        if let Some(marker) = starts_marker(comment, &comment[index..], language) {
+            markers.push(marker);
+        }
+    }
+}
+
+fn skip(chars: &mut Chars, count: usize) {
+    for _ in 0..count {
+        chars.next();
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use language::Language;
+    use language::{Escape, Language};
+
+    #[test]
+    fn code_without_comments() {
+        let mut stack = vec![];
+        let mut quote = None;
+        let language = Language::new_c().set_quotes(vec![("\"", "\"")]);
+
+        let code = code_text("a = \"/*\"; /* b */ c; // d", &language, &mut stack, &mut quote);
+        assert_eq!(code, "a = \"/*\";  c; ");
+        let code = code_text("e /* f", &language, &mut stack, &mut quote);
+        assert_eq!(code, "e ");
+        let code = code_text("g */ h", &language, &mut stack, &mut quote);
+        assert_eq!(code, " h");
+    }
 
     #[test]
     fn both_comments_in_line() {
@@ -110,6 +380,48 @@ mod tests {
         assert_eq!(stack.len(), 0);
     }
 
+    #[test]
+    fn braces_in_code() {
+        let mut stack = vec![];
+        let mut quote = None;
+        let language = Language::new_c();
+        let line = "mod tests { \"}\" /* } */ {";
+        assert_eq!(handle_multi_line(line, &language, &mut stack, &mut quote).braces, 2);
+    }
+
+    #[test]
+    fn decisions_in_code() {
+        let mut stack = vec![];
+        let mut quote = None;
+        let language = Language::new_c()
+            .set_quotes(vec![("\"", "\"")])
+            .decisions(vec!["if", "&&", "||"]);
+        let line = "if (a && b || elif) { \"if\"; } // if";
+        assert_eq!(handle_multi_line(line, &language, &mut stack, &mut quote).decisions, 3);
+    }
+
+    #[test]
+    fn statements_in_code() {
+        let mut stack = vec![];
+        let mut quote = None;
+        let language = Language::new_c()
+            .set_quotes(vec![("\"", "\"")])
+            .terminators(vec![";", "{", "}"]);
+        let line = "for (i = 0; i < 5; i++) { puts(\";\"); } /* ; */";
+        assert_eq!(handle_multi_line(line, &language, &mut stack, &mut quote).statements, 5);
+    }
+
+    #[test]
+    fn markers_in_comments() {
+        let mut stack = vec![];
+        let mut quote = None;
+        let mut language = Language::new_c().set_quotes(vec![("\"", "\"")]);
+        language.marker_tags = vec![String::from("TODO"), String::from("FIXME")];
+        let line = "/* FIXME */ x = \"TODO\"; // TODO: TODOS";
+        let contents = handle_multi_line(line, &language, &mut stack, &mut quote);
+        assert_eq!(contents.markers, vec![1, 0]);
+    }
+
     #[test]
     fn comment_hidden_in_single() {
         let mut stack = vec![];
@@ -157,4 +469,81 @@ mod tests {
                           &mut quote);
         assert_eq!(stack.len(), 1);
     }
+
+    #[test]
+    fn empty_quote() {
+        let mut stack = vec![];
+        let mut quote = None;
+        let language = Language::new_c();
+        handle_multi_line("let x = \"\"; /* Hello", &language, &mut stack, &mut quote);
+        assert_eq!(quote, None);
+        assert_eq!(stack.len(), 1);
+    }
+
+    #[test]
+    fn quotes_of_uneven_length() {
+        let mut stack = vec![];
+        let mut quote = None;
+        let language = Language::new_hash().set_quotes(vec![("\"\"\"", "\"\"\""), ("\"", "\"")]);
+        handle_multi_line("\"\"\"Hello\"\"\"", &language, &mut stack, &mut quote);
+        assert_eq!(quote, None);
+        handle_multi_line("\"\"\"", &language, &mut stack, &mut quote);
+        assert_eq!(quote.map(|quote| quote.end), Some("\"\"\"".into()));
+    }
+
+    #[test]
+    fn raw_quote() {
+        let mut stack = vec![];
+        let mut quote = None;
+        let language = Language::new_c().escaped_quotes(vec![("r#\"", "\"#", Escape::None)]);
+        handle_multi_line("r#\"C:\\\"# /* Hello", &language, &mut stack, &mut quote);
+        assert_eq!(quote, None);
+        assert_eq!(stack.len(), 1);
+    }
+
+    #[test]
+    fn verbatim_quote() {
+        let mut stack = vec![];
+        let mut quote = None;
+        let language = Language::new_c().escaped_quotes(vec![("@\"", "\"", Escape::Doubled)]);
+        handle_multi_line("@\"C:\\ \"\"/*\"\"\" /* Hello", &language, &mut stack, &mut quote);
+        assert_eq!(quote, None);
+        assert_eq!(stack.len(), 1);
+    }
+
+    #[test]
+    fn char_quote_and_lifetime() {
+        let mut stack = vec![];
+        let mut quote = None;
+        let language = Language::new_c().char_quotes(vec![("'", "'")]);
+        handle_multi_line("fn f<'a>(x: &'a str) -> char { '\"' }",
+                          &language,
+                          &mut stack,
+                          &mut quote);
+        assert_eq!(quote, None);
+        handle_multi_line("let c = '\\''; /* Hello", &language, &mut stack, &mut quote);
+        assert_eq!(quote, None);
+        assert_eq!(stack.len(), 1);
+    }
+
+    #[test]
+    fn delimited_quote() {
+        let mut stack = vec![];
+        let mut quote = None;
+        let language = Language::new_c().delimited_quotes(vec![("R\"", "(", ")", "\"")]);
+        handle_multi_line("auto s = R\"sql(/* )\" ", &language, &mut stack, &mut quote);
+        assert_eq!(quote.as_ref().map(|quote| &*quote.end), Some(")sql\""));
+        handle_multi_line(")sql\"; /* Hello", &language, &mut stack, &mut quote);
+        assert_eq!(quote, None);
+        assert_eq!(stack.len(), 1);
+    }
+
+    #[test]
+    fn custom_escape() {
+        let mut stack = vec![];
+        let mut quote = None;
+        let language = Language::new_hash().escaped_quotes(vec![("\"", "\"", Escape::Custom("`"))]);
+        handle_multi_line("\"C:\\\" # `\"\" <# Hello", &language, &mut stack, &mut quote);
+        assert_eq!(quote, None);
+    }
 }
@@ -19,8 +19,8 @@ extern crate toml;
 extern crate tokei;
 
 use std::borrow::Cow;
-#[cfg(feature = "io")]
 use std::collections::BTreeMap;
+use std::cmp::Reverse;
 use std::thread;
 use std::time::Duration;
 use std::sync::mpsc::channel;
@@ -31,7 +31,7 @@ use env_logger::LogBuilder;
 // #[cfg(feature = "cbor")]
 // use rustc_serialize::hex::FromHex;
 
-use tokei::{Languages, Language, LanguageType};
+use tokei::{Duplicates, Languages, Language, LanguageType, MixedPolicy, Stats};
 use tokei::Sort::*;
 const ROW: &'static str = "-------------------------------------------------------------------\
                                 ------------";
@@ -40,6 +40,25 @@ const COMMENTS: &'static str = "comments";
 const CODE: &'static str = "code";
 const FILES: &'static str = "files";
 const LINES: &'static str = "lines";
+const DOCS: &'static str = "docs";
+const LINE_COMMENTS: &'static str = "line_comments";
+const BLOCK_COMMENTS: &'static str = "block_comments";
+const MIXED: &'static str = "mixed";
+const PREPROCESSOR: &'static str = "preprocessor";
+const DISABLED: &'static str = "disabled";
+const TESTS: &'static str = "tests";
+const PRODUCTION: &'static str = "production";
+const COMPLEXITY: &'static str = "complexity";
+const LOGICAL: &'static str = "logical";
+const MARKERS: &'static str = "markers";
+const BYTES: &'static str = "bytes";
+const CHARS: &'static str = "chars";
+const MAX_LINE_LENGTH: &'static str = "max_line_length";
+const AVG_LINE_LENGTH: &'static str = "avg_line_length";
+const LICENSES: &'static str = "licenses";
+const DUPLICATES: &'static str = "duplicates";
+/// The number of the largest repeated blocks that are printed.
+const CLONE_GROUPS: usize = 10;
 #[cfg(not(feature = "io"))]
 const OUTPUT_ERROR: &'static str = "This version of tokei was compiled without any serialization
     formats, to enable serialization, reinstall tokei with the features flag.
@@ -47,6 +66,34 @@ const OUTPUT_ERROR: &'static str = "This version of tokei was compiled without a
         cargo install tokei --features all
 ";
 
+/// Prints the additional columns of a language or file, and ends the line. Languages count their
+/// markers, while files list them, so the number of markers is given separately.
+macro_rules! print_columns {
+    ($columns:expr, $stats:expr, $markers:expr) => {{
+        for column in $columns {
+            print!(" {:>12}", match *column {
+                DOCS => $stats.doc_comments,
+                LINE_COMMENTS => $stats.line_comments,
+                BLOCK_COMMENTS => $stats.block_comments,
+                MIXED => $stats.mixed,
+                PREPROCESSOR => $stats.preprocessor,
+                DISABLED => $stats.disabled,
+                TESTS => $stats.tests,
+                PRODUCTION => $stats.code - $stats.tests,
+                COMPLEXITY => $stats.complexity,
+                LOGICAL => $stats.logical,
+                MARKERS => $markers,
+                BYTES => $stats.bytes,
+                CHARS => $stats.chars,
+                MAX_LINE_LENGTH => $stats.max_line_length,
+                AVG_LINE_LENGTH => $stats.avg_line_length,
+                _ => unreachable!(),
+            });
+        }
+        println!();
+    }}
+}
+
 fn main() {
     // Get options at the beginning, so the program doesn't have to make any extra calls to get the
     // information, and there isn't any magic strings.
@@ -58,6 +105,27 @@ fn main() {
     let language_option = matches.is_present("languages");
     let verbose_option = matches.occurrences_of("verbose");
     let sort_option = matches.value_of("sort");
+    let mixed_option = match matches.value_of("mixed") {
+        Some("comment") => MixedPolicy::Comment,
+        Some("both") => MixedPolicy::Both,
+        _ => MixedPolicy::Code,
+    };
+    let disable_blocks_option = matches.is_present("disable_blocks");
+    let mut columns_option: Vec<&str> = match matches.values_of("columns") {
+        Some(columns) => columns.collect(),
+        None => Vec::new(),
+    };
+    let licenses_option = matches.is_present(LICENSES);
+    let duplicates_option = if matches.is_present(DUPLICATES) {
+        Some(value_t_or_exit!(matches, DUPLICATES, usize))
+    } else {
+        None
+    };
+    if matches.is_present(MARKERS) {
+        columns_option.push(MARKERS);
+    }
+    let marker_tags_option: Option<Vec<String>> = matches.values_of("marker_tags")
+        .map(|tags| tags.map(String::from).collect());
     let ignored_directories = {
         let mut ignored_directories: Vec<&str> = vec![".git"];
         if let Some(user_ignored) = matches.values_of("exclude") {
@@ -89,11 +157,23 @@ fn main() {
 
     let paths: Vec<&str> = matches.values_of("input").unwrap().collect();
 
+    for (_, language) in &mut languages {
+        language.mixed_policy = mixed_option;
+        language.disable_blocks = disable_blocks_option;
+
+        if let Some(ref marker_tags) = marker_tags_option {
+            language.marker_tags = marker_tags.clone();
+        }
+    }
+
     if let Some(input) = input_option {
         add_input(input, &mut languages);
     }
 
-    let mut total = Language::new_blank();
+    let mut total = Stats::new("Total");
+    let mut total_files = 0;
+    let columns = &*columns_option;
+    let row = format!("{}{}", ROW, "-".repeat(13 * columns.len()));
 
     let print_animation = output_option == None;
     let (tx, rx) = channel();
@@ -117,29 +197,33 @@ fn main() {
     languages.get_statistics(paths, ignored_directories);
 
     if output_option == None {
-        println!("{}", ROW);
-        println!(" {:<12} {:>12} {:>12} {:>12} {:>12} {:>12}",
-                 "Language",
-                 "Files",
-                 "Lines",
-                 "Code",
-                 "Comments",
-                 "Blanks");
-        println!("{}", ROW);
+        println!("{}", row);
+        print!(" {:<12} {:>12} {:>12} {:>12} {:>12} {:>12}",
+               "Language",
+               "Files",
+               "Lines",
+               "Code",
+               "Comments",
+               "Blanks");
+        for column in columns {
+            print!(" {:>12}", column_name(column));
+        }
+        println!();
+        println!("{}", row);
     }
 
     for (name, language) in &languages {
         if !language.is_empty() && sort_option == None && output_option == None {
             if files_option {
-                print_language(language, name);
-                println!("{}", ROW);
+                print_language(language, name, columns);
+                println!("{}", row);
 
                 for stat in &language.stats {
-                    println!("{}", stat);
+                    print_stats(stat, columns);
                 }
-                println!("{}", ROW);
+                println!("{}", row);
             } else if output_option == None {
-                print_language(language, name);
+                print_language(language, name, columns);
             }
         }
     }
@@ -147,12 +231,31 @@ fn main() {
     let _ = tx.send(());
     let _ = child.join();
 
+    // Embedded languages aren't part of their parent's counts, but are still
+    // part of the total.
     for (_, language) in &languages {
         if !language.is_empty() {
-            total += language;
+            total_files += language.stats.len();
+
+            for stat in &language.stats {
+                total += stat.summarise();
+            }
         }
     }
 
+    // Sorting consumes the languages, so their licenses are collected first.
+    let licenses = if licenses_option {
+        Some(collect_licenses(&languages))
+    } else {
+        None
+    };
+    // Duplicates aren't part of the serialised output, so they're only found
+    // when they're printed.
+    let duplicates = match output_option {
+        None => duplicates_option.map(|min_lines| languages.duplicates(min_lines)),
+        Some(_) => None,
+    };
+
     if let Some(format) = output_option {
         match_output(format, &languages);
     } else if let Some(sort_category) = sort_option {
@@ -160,8 +263,10 @@ fn main() {
         for (_, ref mut language) in &mut languages {
             match &*sort_category {
                 BLANKS => language.sort_by(Blanks),
+                BYTES => language.sort_by(Bytes),
                 COMMENTS => language.sort_by(Comments),
                 CODE => language.sort_by(Code),
+                COMPLEXITY => language.sort_by(Complexity),
                 FILES => language.sort_by(Files),
                 LINES => language.sort_by(Lines),
                 _ => unreachable!(),
@@ -172,8 +277,10 @@ fn main() {
 
         match &*sort_category {
             BLANKS => languages.sort_by(|a, b| b.1.blanks.cmp(&a.1.blanks)),
+            BYTES => languages.sort_by(|a, b| b.1.bytes.cmp(&a.1.bytes)),
             COMMENTS => languages.sort_by(|a, b| b.1.comments.cmp(&a.1.comments)),
             CODE => languages.sort_by(|a, b| b.1.code.cmp(&a.1.code)),
+            COMPLEXITY => languages.sort_by(|a, b| b.1.complexity.cmp(&a.1.complexity)),
             FILES => languages.sort_by(|a, b| b.1.stats.len().cmp(&a.1.stats.len())),
             LINES => languages.sort_by(|a, b| b.1.lines.cmp(&a.1.lines)),
             _ => unreachable!(),
@@ -182,14 +289,14 @@ fn main() {
         for (name, language) in languages {
             if !language.is_empty() {
                 if !files_option {
-                    print_language(&language, name);
+                    print_language(&language, name, columns);
                 } else {
-                    print_language(&language, name);
-                    println!("{}", ROW);
+                    print_language(&language, name, columns);
+                    println!("{}", row);
                     for file in &language.stats {
-                        println!("{}", file);
+                        print_stats(file, columns);
                     }
-                    println!("{}", ROW);
+                    println!("{}", row);
                 }
             }
         }
@@ -197,19 +304,119 @@ fn main() {
 
     if output_option == None {
         if !files_option {
-            println!("{}", ROW);
+            println!("{}", row);
+        }
+        print!(" {: <18} {: >6} {:>12} {:>12} {:>12} {:>12}",
+               "Total",
+               total_files,
+               total.lines,
+               total.code,
+               total.comments,
+               total.blanks);
+        print_columns!(columns, total, total.markers.len());
+        println!("{}", row);
+
+        if let Some((licenses, unlicensed)) = licenses {
+            print_licenses(&licenses, &unlicensed);
+        }
+
+        if let Some(duplicates) = duplicates {
+            print_duplicates(&duplicates);
         }
-        println!(" {: <18} {: >6} {:>12} {:>12} {:>12} {:>12}",
-                 "Total",
-                 total.stats.len(),
-                 total.lines,
-                 total.code,
-                 total.comments,
-                 total.blanks);
+    }
+}
+
+/// Collects the number of files and lines with each license, and the names of the files without
+/// a license.
+fn collect_licenses(languages: &Languages) -> (BTreeMap<String, (usize, usize)>, Vec<String>) {
+    let mut licenses = BTreeMap::new();
+    let mut unlicensed = Vec::new();
+
+    for (_, language) in languages {
+        for stats in &language.stats {
+            match stats.license {
+                Some(ref license) => {
+                    let totals = licenses.entry(license.clone()).or_insert((0, 0));
+                    totals.0 += 1;
+                    totals.1 += stats.lines;
+                }
+                // Languages without comments, and prose, don't have license
+                // headers, so their files aren't listed.
+                None if !language.is_blank() && language.code_fences.is_empty() => {
+                    unlicensed.push(stats.name.clone())
+                }
+                None => {}
+            }
+        }
+    }
+
+    unlicensed.sort();
+    (licenses, unlicensed)
+}
+
+fn print_licenses(licenses: &BTreeMap<String, (usize, usize)>, unlicensed: &[String]) {
+    println!(" {:<25} {:>12} {:>12}", "License", "Files", "Lines");
+    println!("{}", ROW);
+
+    for (license, &(files, lines)) in licenses {
+        println!(" {:<25} {:>12} {:>12}", license, files, lines);
+    }
+
+    if !unlicensed.is_empty() {
+        println!("{}", ROW);
+        println!(" Files without a license");
         println!("{}", ROW);
+
+        for name in unlicensed {
+            println!(" {}", name);
+        }
     }
+
+    println!("{}", ROW);
 }
 
+fn print_duplicates(duplicates: &BTreeMap<LanguageType, Duplicates>) {
+    println!(" {:<25} {:>12} {:>12} {:>12}", "Language", "Code", "Duplicated", "Percent");
+    println!("{}", ROW);
+
+    let mut groups = Vec::new();
+    for (name, language) in duplicates {
+        if language.code == 0 {
+            continue;
+        }
+
+        println!(" {:<25} {:>12} {:>12} {:>11.2}%",
+                 name.name(),
+                 language.code,
+                 language.duplicated,
+                 language.percentage());
+
+        for group in &language.groups {
+            groups.push((name, group));
+        }
+    }
+
+    if !groups.is_empty() {
+        groups.sort_by_key(|&(_, group)| Reverse(group.lines));
+
+        println!("{}", ROW);
+        println!(" Largest repeated blocks");
+        println!("{}", ROW);
+
+        for &(name, group) in groups.iter().take(CLONE_GROUPS) {
+            println!(" {} lines of {} in {} places",
+                     group.lines,
+                     name.name(),
+                     group.locations.len());
+
+            for location in &group.locations {
+                println!("     {}:{}-{}", location.name, location.start, location.end);
+            }
+        }
+    }
+
+    println!("{}", ROW);
+}
 
 #[cfg(feature = "all")]
 fn add_input(input: &str, languages: &mut Languages) {
@@ -299,14 +506,62 @@ pub fn convert_input(contents: String) -> ! {
     panic!(OUTPUT_ERROR);
 }
 
-fn print_language<'a, C>(language: &'a Language, name: C)
+fn print_language<'a, C>(language: &'a Language, name: C, columns: &[&str])
     where C: Into<Cow<'a, LanguageType>>
 {
-    println!(" {: <18} {: >6} {:>12} {:>12} {:>12} {:>12}",
-             name.into().name(),
-             language.stats.len(),
-             language.lines,
-             language.code,
-             language.comments,
-             language.blanks)
+    print!(" {: <18} {: >6} {:>12} {:>12} {:>12} {:>12}",
+           name.into().name(),
+           language.stats.len(),
+           language.lines,
+           language.code,
+           language.comments,
+           language.blanks);
+    print_columns!(columns, language, language.markers);
+
+    for (name, child) in &language.children {
+        print!("  |- {: <14} {: >6} {:>12} {:>12} {:>12} {:>12}",
+               name.name(),
+               "",
+               child.lines,
+               child.code,
+               child.comments,
+               child.blanks);
+        print_columns!(columns, child, child.markers.len());
+    }
+}
+
+fn print_stats(stats: &Stats, columns: &[&str]) {
+    print!("{}", stats);
+    print_columns!(columns, stats, stats.markers.len());
+
+    for (name, child) in &stats.children {
+        print!("  |- {: <21} {:>12} {:>12} {:>12} {:>12}",
+               name.name(),
+               child.lines,
+               child.code,
+               child.comments,
+               child.blanks);
+        print_columns!(columns, child, child.markers.len());
+    }
+}
+
+fn column_name(column: &str) -> &'static str {
+    match column {
+        DOCS => "Docs",
+        LINE_COMMENTS => "Line",
+        BLOCK_COMMENTS => "Block",
+        MIXED => "Mixed",
+        PREPROCESSOR => "Preprocessor",
+        DISABLED => "Disabled",
+        TESTS => "Tests",
+        PRODUCTION => "Production",
+        COMPLEXITY => "Complexity",
+        LOGICAL => "Logical",
+        MARKERS => "Markers",
+        BYTES => "Bytes",
+        CHARS => "Chars",
+        MAX_LINE_LENGTH => "Max Length",
+        AVG_LINE_LENGTH => "Avg Length",
+        _ => unreachable!(),
+    }
 }